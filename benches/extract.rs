@@ -0,0 +1,104 @@
+//! End-to-end extraction of synthetic packages, run with `cargo bench`.
+//! Output goes to /dev/shm when there is one, so the disk doesn't hide
+//! changes to the writers.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/testing.rs"]
+mod testing;
+
+use testing::TestUnityPackageBuilder;
+
+fn scratch_dir() -> tempfile::TempDir {
+    let shm = Path::new("/dev/shm");
+    if shm.is_dir() {
+        if let Ok(dir) = tempfile::tempdir_in(shm) {
+            return dir;
+        }
+    }
+    tempfile::tempdir().unwrap()
+}
+
+fn small_files(builder: TestUnityPackageBuilder, count: usize) -> TestUnityPackageBuilder {
+    (0..count).fold(builder, |builder, i| {
+        let data = format!("small file {} {}", i, "x".repeat(i % 4096));
+        builder.add_asset(
+            &format!("s{:05}", i),
+            &format!("Assets/Small/{:02}/{:05}.txt", i % 50, i),
+            data.as_bytes(),
+        )
+    })
+}
+
+fn huge_files(builder: TestUnityPackageBuilder, count: usize) -> TestUnityPackageBuilder {
+    (0..count).fold(builder, |builder, i| {
+        builder.add_large_asset(
+            &format!("h{:02}", i),
+            &format!("Assets/Huge/{:02}.bin", i),
+            64 << 20,
+        )
+    })
+}
+
+/// The packages benchmarked, by name.
+fn packages() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        (
+            "many_small",
+            small_files(TestUnityPackageBuilder::new(), 5000).build(),
+        ),
+        (
+            "few_huge",
+            huge_files(TestUnityPackageBuilder::new(), 4).build(),
+        ),
+        (
+            "mixed",
+            huge_files(small_files(TestUnityPackageBuilder::new(), 2000), 2).build(),
+        ),
+    ]
+}
+
+fn extract(input: &Path, output: &Path, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_rust-unityextractor"))
+        .arg("-q")
+        .arg("-o")
+        .arg(output)
+        .args(args)
+        .arg(input)
+        .status()
+        .unwrap();
+    assert!(status.success(), "extraction failed: {}", status);
+}
+
+fn bench_extract(c: &mut Criterion) {
+    let dir = scratch_dir();
+    for (name, package) in packages() {
+        let input: PathBuf = dir.path().join(format!("{}.unitypackage", name));
+        std::fs::write(&input, &package).unwrap();
+        let mut group = c.benchmark_group(name);
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(package.len() as u64));
+        for (variant, args) in [
+            ("default", &[][..]),
+            ("group_by_folder", &["--group-by-folder"][..]),
+            ("mmap", &["--mmap"][..]),
+        ] {
+            group.bench_function(variant, |b| {
+                b.iter_batched(
+                    || tempfile::tempdir_in(dir.path()).unwrap(),
+                    |output| extract(&input, output.path(), args),
+                    BatchSize::PerIteration,
+                )
+            });
+        }
+        group.finish();
+        std::fs::remove_file(&input).unwrap();
+    }
+}
+
+criterion_group!(benches, bench_extract);
+criterion_main!(benches);
@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde_json::{json, Value};
+
+use crate::checksum::{self, Digest};
+
+/// What a manifest knows of an asset.
+#[derive(Clone, Debug, PartialEq)]
+struct ManifestEntry {
+    path_name: String,
+    /// The pathname in the package, when `--case` or `--ascii` changed it.
+    original_path_name: Option<String>,
+    sha256: String,
+    /// Of the file written, which transforms may have changed.
+    file_sha256: Option<String>,
+}
+
+/// `--manifest`: the pathname and SHA-256 of each asset of the packages by
+/// GUID, so the next version can be extracted over them with `--baseline`.
+#[derive(Default)]
+pub struct Manifest {
+    assets: Option<Mutex<BTreeMap<String, ManifestEntry>>>,
+    /// The SHA-256 of each package file, to trace files back to it.
+    inputs: Mutex<Vec<(String, Digest)>>,
+}
+
+impl Manifest {
+    pub fn new(enabled: bool) -> Manifest {
+        Manifest {
+            assets: enabled.then(Default::default),
+            ..Default::default()
+        }
+    }
+
+    pub fn record_input(&self, input_path: &str, digest: &Digest) {
+        if self.assets.is_some() {
+            let mut inputs = self.inputs.lock().unwrap_or_else(|e| e.into_inner());
+            inputs.push((input_path.to_string(), *digest));
+        }
+    }
+
+    pub fn record(&self, guid: &str, path_name: &str, original_path_name: &str, digest: &Digest) {
+        let Some(assets) = &self.assets else {
+            return;
+        };
+        let entry = ManifestEntry {
+            path_name: path_name.to_string(),
+            original_path_name: Some(original_path_name.to_string())
+                .filter(|original| original != path_name),
+            sha256: checksum::to_hex(digest),
+            file_sha256: None,
+        };
+        let mut assets = assets.lock().unwrap_or_else(|e| e.into_inner());
+        assets.insert(guid.to_string(), entry);
+    }
+
+    /// Keeps what `baseline` knows of the file written for an asset left
+    /// alone.
+    pub fn keep(&self, guid: &str, baseline: &Baseline) {
+        let (Some(assets), Some(kept)) = (&self.assets, baseline.assets.get(guid)) else {
+            return;
+        };
+        let mut assets = assets.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = assets.get_mut(guid) {
+            entry.file_sha256.clone_from(&kept.file_sha256);
+        }
+    }
+
+    /// Records what was written for an asset `record` was called for.
+    pub fn record_written(&self, guid: &str, path_name: &str, data: &[u8]) {
+        let Some(assets) = &self.assets else {
+            return;
+        };
+        let mut assets = assets.lock().unwrap_or_else(|e| e.into_inner());
+        // Resources share the GUID of their asset, not its pathname.
+        if let Some(entry) = assets.get_mut(guid).filter(|e| e.path_name == path_name) {
+            entry.file_sha256 = Some(checksum::to_hex(&checksum::sha256(data)));
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), io::Error> {
+        let Some(assets) = &self.assets else {
+            return Ok(());
+        };
+        let assets = assets.lock().unwrap_or_else(|e| e.into_inner());
+        let assets: serde_json::Map<String, Value> = assets
+            .iter()
+            .map(|(guid, entry)| {
+                let mut value = json!({"pathname": entry.path_name, "sha256": entry.sha256});
+                if let Some(original) = &entry.original_path_name {
+                    value["original_pathname"] = json!(original);
+                }
+                if let Some(file_sha256) = &entry.file_sha256 {
+                    value["file_sha256"] = json!(file_sha256);
+                }
+                (guid.clone(), value)
+            })
+            .collect();
+        let inputs = self.inputs.lock().unwrap_or_else(|e| e.into_inner());
+        let manifest = json!({"inputs": inputs_json(&inputs), "assets": assets});
+        std::fs::write(path, serde_json::to_vec_pretty(&manifest)?)
+    }
+}
+
+/// `[{"path", "sha256"}]`, as the manifest and the report list packages.
+pub fn inputs_json(inputs: &[(String, Digest)]) -> Value {
+    inputs
+        .iter()
+        .map(|(path, digest)| json!({"path": path, "sha256": checksum::to_hex(digest)}))
+        .collect()
+}
+
+/// `--baseline`: the manifest of the version already extracted. Assets
+/// with the same GUID, pathname and content are left alone.
+#[derive(Default)]
+pub struct Baseline {
+    assets: HashMap<String, ManifestEntry>,
+    /// Pathnames of the GUIDs met in the packages.
+    seen: Mutex<HashMap<String, String>>,
+}
+
+impl Baseline {
+    pub fn read(path: &Path) -> Result<Baseline, io::Error> {
+        let manifest: Value = serde_json::from_slice(&std::fs::read(path)?)?;
+        let Some(assets) = manifest["assets"].as_object() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a manifest, it has no assets",
+            ));
+        };
+        let assets = assets
+            .iter()
+            .filter_map(|(guid, entry)| {
+                let path_name = entry["pathname"].as_str()?.to_string();
+                let sha256 = entry["sha256"].as_str()?.to_ascii_lowercase();
+                let file_sha256 = entry["file_sha256"].as_str().map(str::to_ascii_lowercase);
+                let original_path_name = entry["original_pathname"].as_str().map(str::to_string);
+                let entry = ManifestEntry {
+                    path_name,
+                    original_path_name,
+                    sha256,
+                    file_sha256,
+                };
+                Some((guid.clone(), entry))
+            })
+            .collect();
+        Ok(Baseline {
+            assets,
+            ..Default::default()
+        })
+    }
+
+    /// Whether the baseline has this asset, as is. Remembers the GUID was
+    /// met either way.
+    pub fn is_unchanged(&self, guid: &str, path_name: &str, digest: &Digest) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.insert(guid.to_string(), path_name.to_string());
+        self.assets.get(guid).is_some_and(|entry| {
+            entry.path_name == path_name && entry.sha256 == checksum::to_hex(digest)
+        })
+    }
+
+    /// Whether the file extracted for this asset was edited since, `data`
+    /// being what it holds now.
+    pub fn is_modified(&self, guid: &str, path_name: &str, data: &[u8]) -> bool {
+        let Some(entry) = self.assets.get(guid).filter(|e| e.path_name == path_name) else {
+            return false;
+        };
+        let expected = entry.file_sha256.as_ref().unwrap_or(&entry.sha256);
+        *expected != checksum::to_hex(&checksum::sha256(data))
+    }
+
+    /// The pathname the manifest has for `guid`, as it was in the package.
+    pub fn path_name(&self, guid: &str) -> Option<&str> {
+        let entry = self.assets.get(guid)?;
+        Some(
+            entry
+                .original_path_name
+                .as_ref()
+                .unwrap_or(&entry.path_name),
+        )
+    }
+
+    /// Pathnames of the baseline no package had anymore, either because
+    /// their GUID is gone or because it moved elsewhere.
+    pub fn removed(&self) -> Vec<String> {
+        let seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let mut removed: Vec<String> = self
+            .assets
+            .iter()
+            .filter(|(guid, entry)| seen.get(*guid) != Some(&entry.path_name))
+            .map(|(_, entry)| entry.path_name.clone())
+            .collect();
+        removed.sort();
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::sha256;
+
+    #[test]
+    fn test_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        let manifest = Manifest::new(true);
+        let record =
+            |guid, path_name, data| manifest.record(guid, path_name, path_name, &sha256(data));
+        record("a1", "Assets/kept.txt", b"kept");
+        record("a2", "Assets/changed.txt", b"v1");
+        record("a3", "Assets/moved.txt", b"moved");
+        record("a4", "Assets/removed.txt", b"removed");
+        manifest.record("a5", "Assets/Cafe.txt", "Assets/Café.txt", &sha256(b"cafe"));
+        manifest.record_written("a2", "Assets/changed.txt", b"v1\r\n");
+        manifest.record_written("a2", "Assets/changed.txt.resS", b"resource");
+        manifest.record_input("v1.unitypackage", &sha256(b"package"));
+        manifest.write(&path).unwrap();
+        let written: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            written["inputs"],
+            json!([{"path": "v1.unitypackage", "sha256": checksum::to_hex(&sha256(b"package"))}])
+        );
+        assert_eq!(
+            written["assets"]["a5"]["original_pathname"],
+            "Assets/Café.txt"
+        );
+        assert!(written["assets"]["a1"].get("original_pathname").is_none());
+
+        let baseline = Baseline::read(&path).unwrap();
+        assert!(baseline.is_unchanged("a1", "Assets/kept.txt", &sha256(b"kept")));
+        assert!(!baseline.is_unchanged("a2", "Assets/changed.txt", &sha256(b"v2")));
+        assert!(!baseline.is_unchanged("a3", "Assets/Moved/moved.txt", &sha256(b"moved")));
+        assert!(!baseline.is_unchanged("a6", "Assets/new.txt", &sha256(b"new")));
+        assert!(!baseline.is_modified("a2", "Assets/changed.txt", b"v1\r\n"));
+        assert!(baseline.is_modified("a2", "Assets/changed.txt", b"v1"));
+        assert!(baseline.is_modified("a1", "Assets/kept.txt", b"edited"));
+        assert!(!baseline.is_modified("a6", "Assets/new.txt", b"edited"));
+        assert_eq!(baseline.path_name("a5"), Some("Assets/Café.txt"));
+        assert_eq!(baseline.path_name("a6"), None);
+        assert_eq!(
+            baseline.removed(),
+            vec!["Assets/Cafe.txt", "Assets/moved.txt", "Assets/removed.txt"]
+        );
+
+        std::fs::write(&path, b"[]").unwrap();
+        assert!(Baseline::read(&path).is_err());
+    }
+}
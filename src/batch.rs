@@ -0,0 +1,87 @@
+use std::any::Any;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::summary::ExtractedAsset;
+use crate::AssetWriteError;
+
+/// Assets up to this size are written in batches rather than by a task of
+/// their own.
+pub const SMALL_FILE_SIZE: usize = 16 * 1024;
+const BATCH_FILES: usize = 64;
+const BATCH_BYTES: usize = 1024 * 1024;
+
+pub type WriteResult = Result<ExtractedAsset, AssetWriteError>;
+
+/// A queued write: a task of its own, or a place in a batch of small files.
+pub(crate) enum WriteTask {
+    Spawned(JoinHandle<WriteResult>),
+    Batched(oneshot::Receiver<WriteResult>),
+}
+
+impl Future for WriteTask {
+    type Output = Result<WriteResult, io::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.get_mut() {
+            WriteTask::Spawned(handle) => Pin::new(handle).poll(cx).map_err(io::Error::other),
+            WriteTask::Batched(receiver) => Pin::new(receiver)
+                .poll(cx)
+                .map_err(|_| io::Error::other("the batch was dropped before writing")),
+        }
+    }
+}
+
+pub struct BatchedFile {
+    pub asset_data: Vec<u8>,
+    pub path_name: String,
+    pub guid_name: String,
+    /// For `--trace-out`'s queue phase.
+    pub queued: Instant,
+    pub sender: oneshot::Sender<WriteResult>,
+}
+
+/// Small files waiting to be written together, in archive order.
+#[derive(Default)]
+pub struct Batch {
+    files: Vec<BatchedFile>,
+    bytes: usize,
+}
+
+impl Batch {
+    /// Queues a file, returning the handle its result comes through.
+    pub fn push(&mut self, asset_data: Vec<u8>, path_name: String, guid_name: String) -> WriteTask {
+        let (sender, receiver) = oneshot::channel();
+        self.bytes += asset_data.len();
+        self.files.push(BatchedFile {
+            asset_data,
+            path_name,
+            guid_name,
+            queued: Instant::now(),
+            sender,
+        });
+        WriteTask::Batched(receiver)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.files.len() >= BATCH_FILES || self.bytes >= BATCH_BYTES
+    }
+
+    pub fn into_files(self) -> Vec<BatchedFile> {
+        self.files
+    }
+}
+
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panicked".to_string())
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use log::warn;
+use sha2::{Digest as _, Sha256};
+
+pub type Digest = [u8; 32];
+
+pub fn sha256(data: &[u8]) -> Digest {
+    Sha256::digest(data).into()
+}
+
+/// The digest of everything `reader` has left, read a block at a time.
+pub fn sha256_reader(mut reader: impl Read) -> Result<Digest, io::Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Hashes what is read through it, so a package is checksummed while it's
+/// decoded rather than read twice.
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R) -> HashingReader<R> {
+        HashingReader {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The digest of the whole input, hashing what the decoder left unread.
+    pub fn finish(mut self) -> Result<Digest, io::Error> {
+        io::copy(&mut self, &mut io::sink())?;
+        Ok(self.hasher.finalize().into())
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+pub fn to_hex(digest: &Digest) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parses a `checksums` entry, written like `sha256sum` output so a raw
+/// extraction can be checked with `sha256sum -c`: `<sha256>  <guid>/asset`.
+/// Returns the expected lowercase digest of each GUID.
+pub fn parse_manifest(text: &str) -> HashMap<PathBuf, String> {
+    let mut checksums = HashMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let parsed = line
+            .split_once(char::is_whitespace)
+            .and_then(|(hex, path)| {
+                let path = path.trim_start().trim_start_matches('*');
+                let guid = path.strip_suffix("/asset").unwrap_or(path);
+                let valid = hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit());
+                (valid && !guid.is_empty()).then(|| (PathBuf::from(guid), hex.to_ascii_lowercase()))
+            });
+        match parsed {
+            Some((guid, hex)) => {
+                checksums.insert(guid, hex);
+            }
+            None => warn!("ignoring malformed checksums line {:?}", line),
+        }
+    }
+    checksums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let digest = to_hex(&sha256(b"data"));
+        assert_eq!(
+            digest,
+            "3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7"
+        );
+        let manifest = format!(
+            "{}  a1/asset\n{} *a2\nnot a checksum\n\n",
+            digest,
+            digest.to_uppercase()
+        );
+        let checksums = parse_manifest(&manifest);
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(checksums[&PathBuf::from("a1")], digest);
+        assert_eq!(checksums[&PathBuf::from("a2")], digest);
+    }
+
+    #[test]
+    fn test_hashing_reader() {
+        let mut reader = HashingReader::new(b"data and more".as_slice());
+        let mut start = [0; 4];
+        reader.read_exact(&mut start).unwrap();
+        assert_eq!(&start, b"data");
+        assert_eq!(reader.finish().unwrap(), sha256(b"data and more"));
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+
+use log::warn;
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether output should be colored: not disabled by `--no-color` or the
+/// `NO_COLOR` convention, and going to a terminal.
+pub fn use_color(no_color: bool) -> bool {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && !no_color_env && io::stdout().is_terminal()
+}
+
+/// One line per extracted file, shown instead of log lines at the default
+/// verbosity.
+#[derive(Clone, Copy, Default)]
+pub struct StatusLine {
+    enabled: bool,
+    /// `--summary`: only failed files get a line.
+    failures_only: bool,
+    color: bool,
+}
+
+impl StatusLine {
+    pub fn new(enabled: bool, color: bool) -> StatusLine {
+        StatusLine {
+            enabled,
+            failures_only: false,
+            color,
+        }
+    }
+
+    pub fn failures_only(color: bool) -> StatusLine {
+        StatusLine {
+            enabled: true,
+            failures_only: true,
+            color,
+        }
+    }
+
+    fn print(&self, mark: char, color: &str, line: &str) {
+        let mut stdout = io::stdout().lock();
+        let _ = if self.color {
+            writeln!(stdout, "{}{}{} {}", color, mark, RESET, line)
+        } else {
+            writeln!(stdout, "{} {}", mark, line)
+        };
+    }
+
+    pub fn shows_extracted(&self) -> bool {
+        self.enabled && !self.failures_only
+    }
+
+    /// `held_back` is how many extracted files `--log-interval` didn't show
+    /// since the last line.
+    pub fn extracted(&self, path: &str, held_back: u64) {
+        if !self.shows_extracted() {
+            return;
+        }
+        if held_back > 0 {
+            self.print('✔', GREEN, &format!("{} (+{} more)", path, held_back));
+        } else {
+            self.print('✔', GREEN, path);
+        }
+    }
+
+    pub fn failed(&self, path: &str, reason: impl Display) {
+        if self.enabled {
+            self.print('✖', RED, &format!("{} ({})", path, reason));
+        } else {
+            warn!("failed to write asset: {:?}: {}", path, reason);
+        }
+    }
+}
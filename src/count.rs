@@ -0,0 +1,93 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use flate2::read::GzDecoder;
+use log::warn;
+use serde_json::{json, Value};
+
+use crate::guid_of;
+
+/// `--count`: how many files and folders a package extracts to, from the tar
+/// headers and the pathname entries alone. Asset data is decompressed, as
+/// gzip has to, but skipped instead of read.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PackageCount {
+    pub files: u64,
+    pub folders: u64,
+    pub bytes: u64,
+}
+
+impl PackageCount {
+    pub fn add(&mut self, other: PackageCount) {
+        self.files += other.files;
+        self.folders += other.folders;
+        self.bytes += other.bytes;
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({"files": self.files, "folders": self.folders, "bytes": self.bytes})
+    }
+}
+
+pub fn count_package<R: Read>(reader: R) -> Result<PackageCount, io::Error> {
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let mut path_names: HashSet<PathBuf> = HashSet::new();
+
+    for entry_result in archive.entries()? {
+        let mut entry = match entry_result {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("error reading entry from archive: {}", e);
+                continue;
+            }
+        };
+        let path = entry.path()?.to_path_buf();
+        let guid = guid_of(&path).to_path_buf();
+        if path.ends_with("asset") {
+            sizes.insert(guid, entry.header().size()?);
+        } else if path.ends_with("pathname") {
+            // Its content doesn't matter, only that the GUID has one.
+            io::copy(&mut entry, &mut io::sink())?;
+            path_names.insert(guid);
+        }
+    }
+
+    let mut count = PackageCount::default();
+    for guid in path_names {
+        match sizes.get(&guid) {
+            Some(size) => {
+                count.files += 1;
+                count.bytes += size;
+            }
+            None => count.folders += 1,
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestUnityPackageBuilder;
+
+    #[test]
+    fn test_count_package() {
+        let package = TestUnityPackageBuilder::new()
+            .add_folder("f1", "Assets/Rocks")
+            .add_asset("a1", "Assets/Rocks/a.txt", b"a")
+            .add_large_asset("a2", "Assets/Rocks/b.bin", 3 << 20)
+            .add_entry("a3/asset", b"orphan")
+            .build();
+        let count = count_package(package.as_slice()).unwrap();
+        assert_eq!(
+            count,
+            PackageCount {
+                files: 2,
+                folders: 1,
+                bytes: (3 << 20) + 1,
+            }
+        );
+    }
+}
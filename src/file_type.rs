@@ -0,0 +1,146 @@
+use std::fmt;
+
+/// How many leading bytes are inspected to tell text from binary data.
+const TEXT_SNIFF_LEN: usize = 8192;
+
+/// Content type of an asset, guessed from its leading bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FileType {
+    Png,
+    Jpeg,
+    Gif,
+    Psd,
+    Fbx,
+    Wav,
+    Ogg,
+    Mp3,
+    Pe,
+    Elf,
+    MachO,
+    Zip,
+    UnityYaml,
+    Text,
+    Binary,
+    Empty,
+}
+
+impl FileType {
+    pub fn detect(data: &[u8]) -> FileType {
+        let starts = |magic: &[u8]| data.starts_with(magic);
+
+        if data.is_empty() {
+            FileType::Empty
+        } else if starts(b"\x89PNG\r\n\x1a\n") {
+            FileType::Png
+        } else if starts(b"\xff\xd8\xff") {
+            FileType::Jpeg
+        } else if starts(b"GIF87a") || starts(b"GIF89a") {
+            FileType::Gif
+        } else if starts(b"8BPS") {
+            FileType::Psd
+        } else if starts(b"Kaydara FBX Binary") || starts(b"; FBX") {
+            FileType::Fbx
+        } else if starts(b"RIFF") && data.get(8..12) == Some(b"WAVE") {
+            FileType::Wav
+        } else if starts(b"OggS") {
+            FileType::Ogg
+        } else if starts(b"ID3") || starts(b"\xff\xfb") || starts(b"\xff\xf3") {
+            FileType::Mp3
+        } else if starts(b"MZ") {
+            FileType::Pe
+        } else if starts(b"\x7fELF") {
+            FileType::Elf
+        } else if starts(b"\xcf\xfa\xed\xfe")
+            || starts(b"\xce\xfa\xed\xfe")
+            || starts(b"\xca\xfe\xba\xbe")
+        {
+            FileType::MachO
+        } else if starts(b"PK\x03\x04") {
+            FileType::Zip
+        } else if starts(b"%YAML") {
+            FileType::UnityYaml
+        } else if is_text(&data[..data.len().min(TEXT_SNIFF_LEN)]) {
+            FileType::Text
+        } else {
+            FileType::Binary
+        }
+    }
+
+    /// A plausible file extension for assets of this type.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            FileType::Png => Some("png"),
+            FileType::Jpeg => Some("jpg"),
+            FileType::Gif => Some("gif"),
+            FileType::Psd => Some("psd"),
+            FileType::Fbx => Some("fbx"),
+            FileType::Wav => Some("wav"),
+            FileType::Ogg => Some("ogg"),
+            FileType::Mp3 => Some("mp3"),
+            FileType::Pe => Some("dll"),
+            FileType::Elf => Some("so"),
+            FileType::MachO => Some("dylib"),
+            FileType::Zip => Some("zip"),
+            FileType::UnityYaml => Some("asset"),
+            FileType::Text => Some("txt"),
+            FileType::Binary | FileType::Empty => None,
+        }
+    }
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FileType::Png => "PNG",
+            FileType::Jpeg => "JPEG",
+            FileType::Gif => "GIF",
+            FileType::Psd => "PSD",
+            FileType::Fbx => "FBX",
+            FileType::Wav => "WAV",
+            FileType::Ogg => "Ogg",
+            FileType::Mp3 => "MP3",
+            FileType::Pe => "PE library",
+            FileType::Elf => "ELF library",
+            FileType::MachO => "Mach-O library",
+            FileType::Zip => "zip",
+            FileType::UnityYaml => "Unity YAML",
+            FileType::Text => "text",
+            FileType::Binary => "binary",
+            FileType::Empty => "empty",
+        };
+        f.write_str(name)
+    }
+}
+
+fn is_text(data: &[u8]) -> bool {
+    if data.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(data) {
+        Ok(_) => true,
+        // A multi-byte character may have been cut at the sniffing boundary.
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(FileType::detect(b""), FileType::Empty);
+        assert_eq!(FileType::detect(b"\x89PNG\r\n\x1a\n\0\0"), FileType::Png);
+        assert_eq!(FileType::detect(b"RIFF\0\0\0\0WAVEfmt "), FileType::Wav);
+        assert_eq!(FileType::detect(b"RIFF\0\0\0\0AVI "), FileType::Binary);
+        assert_eq!(FileType::detect(b"Kaydara FBX Binary  \0"), FileType::Fbx);
+        assert_eq!(FileType::detect(b"%YAML 1.1\n"), FileType::UnityYaml);
+        assert_eq!(
+            FileType::detect("using UnityEngine; // é".as_bytes()),
+            FileType::Text
+        );
+        assert_eq!(FileType::detect(b"\x01\x02\0\x03"), FileType::Binary);
+        assert_eq!(FileType::Png.extension(), Some("png"));
+        assert_eq!(FileType::Binary.extension(), None);
+    }
+}
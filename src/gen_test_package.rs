@@ -0,0 +1,192 @@
+//! `gen-test-package`, a subcommand left out of `--help` that writes stress
+//! packages, to reproduce performance complaints and check their fixes.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use argparse::{ArgumentParser, Store, StoreOption};
+
+use crate::testing::TestUnityPackageBuilder;
+
+pub const COMMAND: &str = "gen-test-package";
+
+/// Size of each `sparse` asset when `--size` isn't given.
+const SPARSE_SIZE: u64 = 64 << 30;
+
+/// Folder and file names for `unicode` packages: other scripts, emoji,
+/// a combining accent and a right-to-left name.
+const UNICODE_NAMES: &[&str] = &[
+    "日本語のテクスチャ",
+    "Ünïcödé",
+    "Ελληνικά",
+    "кириллица",
+    "العربية",
+    "emoji 🎮🪨",
+    "combining e\u{301}",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StressKind {
+    /// Many one line text files, for the per-file overhead.
+    TinyFiles,
+    /// Huge assets of a single repeated byte, which compress to about a
+    /// thousandth of their size.
+    Sparse,
+    /// Paths hundreds of bytes long and dozens of folders deep.
+    DeepPaths,
+    /// Names outside of ASCII.
+    Unicode,
+}
+
+impl FromStr for StressKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tiny-files" => Ok(StressKind::TinyFiles),
+            "sparse" => Ok(StressKind::Sparse),
+            "deep-paths" => Ok(StressKind::DeepPaths),
+            "unicode" => Ok(StressKind::Unicode),
+            _ => Err(format!(
+                "unknown package kind {:?}, expected tiny-files, sparse, deep-paths or unicode",
+                s
+            )),
+        }
+    }
+}
+
+impl StressKind {
+    fn default_count(self) -> usize {
+        match self {
+            StressKind::TinyFiles => 100_000,
+            StressKind::Sparse => 1,
+            StressKind::DeepPaths | StressKind::Unicode => 1_000,
+        }
+    }
+}
+
+/// A package of `count` assets of `kind`, `size` bytes each for `sparse`.
+pub fn stress_package(kind: StressKind, count: usize, size: u64) -> TestUnityPackageBuilder {
+    (0..count).fold(TestUnityPackageBuilder::new(), |builder, i| {
+        let guid = format!("{:032x}", i);
+        match kind {
+            StressKind::TinyFiles => builder.add_asset(
+                &guid,
+                &format!("Assets/Tiny/{:03}/{:06}.txt", i % 1000, i),
+                format!("{}\n", i).as_bytes(),
+            ),
+            StressKind::Sparse => {
+                builder.add_large_asset(&guid, &format!("Assets/Sparse/{:03}.bin", i), size)
+            }
+            StressKind::DeepPaths => {
+                let folders: Vec<String> = (0..8 + i % 56)
+                    .map(|depth| format!("Deep{:02}", depth))
+                    .collect();
+                let path_name = format!(
+                    "Assets/{}/{:06}{}.txt",
+                    folders.join("/"),
+                    i,
+                    "n".repeat(190)
+                );
+                builder.add_asset(&guid, &path_name, b"deep")
+            }
+            StressKind::Unicode => {
+                let folder = UNICODE_NAMES[i % UNICODE_NAMES.len()];
+                let name = UNICODE_NAMES[i / UNICODE_NAMES.len() % UNICODE_NAMES.len()];
+                let path_name = format!("Assets/{}/{} {:04}.txt", folder, name, i);
+                builder.add_asset(&guid, &path_name, name.as_bytes())
+            }
+        }
+    })
+}
+
+/// Runs `gen-test-package`, `args` starting with the program name.
+pub fn run(args: Vec<String>) -> ExitCode {
+    let mut kind = String::new();
+    let mut output = PathBuf::new();
+    let mut count: Option<usize> = None;
+    let mut size = SPARSE_SIZE;
+    {
+        let mut parser = ArgumentParser::new();
+        parser
+            .set_description("Writes a stress package: tiny-files, sparse, deep-paths or unicode.");
+        parser
+            .refer(&mut kind)
+            .add_argument("kind", Store, "the kind of package")
+            .required();
+        parser
+            .refer(&mut output)
+            .add_argument("output", Store, "the .unitypackage to write")
+            .required();
+        parser.refer(&mut count).add_option(
+            &["--count"],
+            StoreOption,
+            "number of assets; 100000 tiny files, 1 sparse asset, 1000 otherwise by default.",
+        );
+        parser.refer(&mut size).add_option(
+            &["--size"],
+            Store,
+            "size of each sparse asset in bytes, 64 GiB by default.",
+        );
+        if let Err(status) = parser.parse(args, &mut std::io::stdout(), &mut std::io::stderr()) {
+            return ExitCode::from(status as u8);
+        }
+    }
+
+    let kind = match kind.parse::<StressKind>() {
+        Ok(kind) => kind,
+        Err(e) => {
+            eprintln!("{}: {}", COMMAND, e);
+            return ExitCode::from(2);
+        }
+    };
+    let builder = stress_package(kind, count.unwrap_or(kind.default_count()), size);
+    let written = File::create(&output)
+        .map(BufWriter::new)
+        .and_then(|writer| builder.write_to(writer))
+        .and_then(|mut writer| writer.flush());
+    match written {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}: cannot write {}: {}", COMMAND, output.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::picker::index_package;
+    use crate::volumes::Volumes;
+
+    #[test]
+    fn test_stress_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        for (kind, size) in [
+            ("tiny-files", None),
+            ("sparse", Some(3 << 20)),
+            ("deep-paths", None),
+            ("unicode", None),
+        ] {
+            let path = dir.path().join(format!("{}.unitypackage", kind));
+            let mut args = vec![COMMAND.to_string(), kind.to_string()];
+            args.push(path.to_str().unwrap().to_string());
+            args.extend(["--count".to_string(), "20".to_string()]);
+            if let Some(size) = size {
+                args.extend(["--size".to_string(), size.to_string()]);
+            }
+            assert_eq!(run(args), ExitCode::SUCCESS, "{}", kind);
+
+            let index = index_package(Volumes::open(path.to_str().unwrap()).unwrap()).unwrap();
+            assert_eq!(index.len(), 20, "{}", kind);
+            if let Some(size) = size {
+                assert!(index.iter().all(|(_, found)| *found == Some(size)));
+            }
+        }
+        assert!("huge".parse::<StressKind>().is_err());
+    }
+}
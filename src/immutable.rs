@@ -0,0 +1,109 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::checksum::{self, Digest};
+use crate::volumes::Volumes;
+
+/// Where `--immutable` puts packages below the output directory, like
+/// Unity does for the packages of its package manager.
+pub const PACKAGE_CACHE: &str = "Library/PackageCache";
+
+/// Hex digits of the package's SHA-256 in its folder name.
+const HASH_LENGTH: usize = 12;
+
+/// `--immutable`: a package extracted into its own read-only folder named
+/// after its content, `<name>@<hash>`, which a run never changes once it
+/// exists. It is filled under another name and renamed when complete.
+#[derive(Debug, PartialEq)]
+pub struct ImmutableDir {
+    pub path: PathBuf,
+    pub partial_path: PathBuf,
+}
+
+impl ImmutableDir {
+    pub fn new(output_dir: &Path, name: &str, digest: &Digest) -> ImmutableDir {
+        let hash = checksum::to_hex(digest);
+        let folder = format!("{}@{}", name, &hash[..HASH_LENGTH]);
+        let cache = output_dir.join(PACKAGE_CACHE);
+        ImmutableDir {
+            partial_path: cache.join(format!(".{}.partial", folder)),
+            path: cache.join(folder),
+        }
+    }
+
+    /// The folder of `input_path` in the cache of `output_dir`.
+    pub fn for_package(
+        output_dir: &Path,
+        input_path: &str,
+        name: &str,
+    ) -> Result<ImmutableDir, io::Error> {
+        let digest = checksum::sha256_reader(Volumes::open(input_path)?)?;
+        Ok(ImmutableDir::new(output_dir, name, &digest))
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.is_dir()
+    }
+
+    /// Removes what an interrupted run left, to extract from scratch.
+    pub fn clear_partial(&self) -> Result<(), io::Error> {
+        match std::fs::remove_dir_all(&self.partial_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Makes the extracted files read-only and moves them in place.
+    pub fn seal(&self) -> Result<(), io::Error> {
+        set_read_only(&self.partial_path)?;
+        std::fs::rename(&self.partial_path, &self.path)
+    }
+}
+
+/// Folders stay writable so the cache can still be cleaned up.
+fn set_read_only(dir: &Path) -> Result<(), io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            set_read_only(&entry.path())?;
+        } else if file_type.is_file() {
+            let mut permissions = entry.metadata()?.permissions();
+            permissions.set_readonly(true);
+            std::fs::set_permissions(entry.path(), permissions)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_immutable_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let input_path = dir.path().join("Rocks.unitypackage");
+        std::fs::write(&input_path, b"package").unwrap();
+        let immutable =
+            ImmutableDir::for_package(dir.path(), input_path.to_str().unwrap(), "Rocks").unwrap();
+        let hash = checksum::to_hex(&checksum::sha256(b"package"));
+        let cache = dir.path().join(PACKAGE_CACHE);
+        assert_eq!(immutable.path, cache.join(format!("Rocks@{}", &hash[..12])));
+        assert!(!immutable.exists());
+
+        immutable.clear_partial().unwrap();
+        let asset = immutable.partial_path.join("Assets/a.txt");
+        std::fs::create_dir_all(asset.parent().unwrap()).unwrap();
+        std::fs::write(&asset, b"a").unwrap();
+        immutable.seal().unwrap();
+        assert!(immutable.exists());
+        assert!(!immutable.partial_path.exists());
+        let sealed = immutable.path.join("Assets/a.txt");
+        assert!(std::fs::metadata(sealed).unwrap().permissions().readonly());
+
+        std::fs::create_dir_all(immutable.partial_path.join("Assets")).unwrap();
+        immutable.clear_partial().unwrap();
+        assert!(!immutable.partial_path.exists());
+    }
+}
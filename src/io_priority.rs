@@ -0,0 +1,108 @@
+use std::io;
+
+/// Whether this platform has a way to lower the I/O priority of a process.
+pub const SUPPORTED: bool = cfg!(any(target_os = "linux", target_os = "macos", windows));
+
+/// `--background`: gives the extractor's reads and writes the idle I/O
+/// class (`ionice -c 3`), so they only get the disk when nothing else wants
+/// it. The priority belongs to each thread, so every thread running now
+/// gets it, and the threads they start later inherit it.
+#[cfg(target_os = "linux")]
+pub fn lower() -> Result<(), io::Error> {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    for task in std::fs::read_dir("/proc/self/task")? {
+        let Some(tid) = task?
+            .file_name()
+            .to_str()
+            .and_then(|t| t.parse::<libc::pid_t>().ok())
+        else {
+            continue;
+        };
+        let priority = IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT;
+        let result =
+            unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, priority) };
+        if result == -1 {
+            let error = io::Error::last_os_error();
+            // The thread may have exited since it was listed.
+            if error.raw_os_error() != Some(libc::ESRCH) {
+                return Err(error);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `--background`: throttles the process's disk I/O, as background QoS
+/// does.
+#[cfg(target_os = "macos")]
+pub fn lower() -> Result<(), io::Error> {
+    const IOPOL_TYPE_DISK: i32 = 0;
+    const IOPOL_SCOPE_PROCESS: i32 = 0;
+    const IOPOL_THROTTLE: i32 = 3;
+
+    extern "C" {
+        fn setiopolicy_np(iotype: i32, scope: i32, policy: i32) -> i32;
+    }
+    if unsafe { setiopolicy_np(IOPOL_TYPE_DISK, IOPOL_SCOPE_PROCESS, IOPOL_THROTTLE) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// `--background`: puts the process in background mode, lowering its I/O
+/// and memory priority.
+#[cfg(windows)]
+pub fn lower() -> Result<(), io::Error> {
+    const PROCESS_MODE_BACKGROUND_BEGIN: u32 = 0x0010_0000;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> *mut std::ffi::c_void;
+        fn SetPriorityClass(process: *mut std::ffi::c_void, priority_class: u32) -> i32;
+    }
+    if unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn lower() -> Result<(), io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the I/O priority can't be lowered on this platform",
+    ))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    const CHILD: &str = "IO_PRIORITY_TEST_CHILD";
+
+    /// Lowers the priority in a run of this test alone, as nothing brings it
+    /// back up for the tests running next.
+    #[test]
+    fn test_lower() {
+        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+        let class = || unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, 0) } >> 13;
+
+        if std::env::var_os(CHILD).is_none() {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", "io_priority::tests::test_lower"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{:?}", output);
+            assert_ne!(class(), 3);
+            return;
+        }
+        lower().unwrap();
+        assert_eq!(class(), 3);
+        // Threads started afterwards inherit it.
+        assert_eq!(std::thread::spawn(class).join().unwrap(), 3);
+    }
+}
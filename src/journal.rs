@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+
+const JOURNAL_NAME: &str = ".unityextractor-journal";
+
+/// Files written so far, one JSON line each in the output directory, so an
+/// interrupted run can be picked up again with `--resume`. The journal is
+/// removed once a run completes.
+#[derive(Default)]
+pub struct Journal {
+    resume: bool,
+    files: Mutex<HashMap<PathBuf, File>>,
+    /// Per output directory, what a previous run wrote with its size.
+    completed: Mutex<HashMap<PathBuf, HashMap<String, u64>>>,
+    skipped: AtomicU64,
+}
+
+fn read_journal(path: &Path) -> Result<HashMap<String, u64>, io::Error> {
+    let mut completed = HashMap::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        // The last line may be cut short by a crash.
+        let Ok(entry) = serde_json::from_str::<Value>(&line?) else {
+            continue;
+        };
+        if let (Some(path), Some(size)) = (entry["path"].as_str(), entry["size"].as_u64()) {
+            completed.insert(path.to_string(), size);
+        }
+    }
+    Ok(completed)
+}
+
+impl Journal {
+    pub fn new(resume: bool) -> Journal {
+        Journal {
+            resume,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_resuming(&self) -> bool {
+        self.resume
+    }
+
+    /// Whether a previous run already wrote this file, and it is still on
+    /// disk with the same size.
+    pub fn is_done(&self, output_dir: &Path, path: &str) -> bool {
+        if !self.resume {
+            return false;
+        }
+        let mut completed = self.completed.lock().unwrap_or_else(|e| e.into_inner());
+        let completed = completed
+            .entry(output_dir.to_path_buf())
+            .or_insert_with(|| match read_journal(&output_dir.join(JOURNAL_NAME)) {
+                Ok(completed) => completed,
+                Err(e) => {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        warn!("cannot read the journal in {}: {}", output_dir.display(), e);
+                    }
+                    HashMap::new()
+                }
+            });
+        let Some(&size) = completed.get(path) else {
+            return false;
+        };
+        let on_disk = std::fs::metadata(output_dir.join(path)).map(|m| m.len());
+        if on_disk.ok() != Some(size) {
+            return false;
+        }
+        debug!("{} was extracted by a previous run", path);
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    pub fn record(&self, output_dir: &Path, path: &str, size: u64) {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let journal_path = output_dir.join(JOURNAL_NAME);
+        if !files.contains_key(output_dir) {
+            // Without --resume, a journal left behind describes another run.
+            let file = File::options()
+                .create(true)
+                .append(self.resume)
+                .write(true)
+                .truncate(!self.resume)
+                .open(&journal_path);
+            match file {
+                Ok(file) => files.insert(output_dir.to_path_buf(), file),
+                Err(e) => {
+                    warn!("cannot write the journal {}: {}", journal_path.display(), e);
+                    return;
+                }
+            };
+        }
+        let line = json!({ "path": path, "size": size }).to_string();
+        let file = files.get_mut(output_dir).expect("journal was just opened");
+        if let Err(e) = writeln!(file, "{}", line) {
+            warn!("cannot write the journal {}: {}", journal_path.display(), e);
+        }
+    }
+
+    /// Removes the journals once a run is over, unless it should be resumed.
+    pub fn finish(&self, keep: bool) {
+        let files = std::mem::take(&mut *self.files.lock().unwrap_or_else(|e| e.into_inner()));
+        let completed =
+            std::mem::take(&mut *self.completed.lock().unwrap_or_else(|e| e.into_inner()));
+        let skipped = self.skipped.swap(0, Ordering::Relaxed);
+        if skipped > 0 {
+            info!("skipped {} files extracted by a previous run", skipped);
+        }
+        let output_dirs: HashSet<PathBuf> =
+            files.into_keys().chain(completed.into_keys()).collect();
+        for output_dir in output_dirs {
+            let journal_path = output_dir.join(JOURNAL_NAME);
+            if keep {
+                info!(
+                    "run again with --resume to skip what is listed in {}",
+                    journal_path.display()
+                );
+            } else if let Err(e) = std::fs::remove_file(&journal_path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    warn!("cannot remove {}: {}", journal_path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume() {
+        let output = tempfile::tempdir().unwrap();
+        std::fs::write(output.path().join("a.txt"), b"aaa").unwrap();
+        std::fs::write(output.path().join("b.txt"), b"bb").unwrap();
+
+        let journal = Journal::new(false);
+        assert!(!journal.is_done(output.path(), "a.txt"));
+        journal.record(output.path(), "a.txt", 3);
+        journal.record(output.path(), "b.txt", 3);
+        journal.finish(true);
+        let mut file = File::options()
+            .append(true)
+            .open(output.path().join(JOURNAL_NAME))
+            .unwrap();
+        write!(file, "{{\"path\": \"c.t").unwrap();
+
+        let journal = Journal::new(true);
+        assert!(journal.is_done(output.path(), "a.txt"));
+        // Sizes that don't match mean the write didn't complete.
+        assert!(!journal.is_done(output.path(), "b.txt"));
+        assert!(!journal.is_done(output.path(), "c.txt"));
+        journal.finish(false);
+        assert!(!output.path().join(JOURNAL_NAME).exists());
+    }
+}
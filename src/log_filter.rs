@@ -0,0 +1,79 @@
+use std::str::FromStr;
+
+use log::LevelFilter;
+use simple_logger::SimpleLogger;
+
+const CRATE: &str = "rust_unityextractor";
+
+/// `--log-filter`, or `RUST_LOG`: comma separated `module=level` items, and
+/// a bare `level` for everything else. Modules are named as in the source,
+/// `main` being the extraction itself and the modules without a filter.
+#[derive(Debug, Default, PartialEq)]
+pub struct LogFilter {
+    pub level: Option<LevelFilter>,
+    /// Log targets and their levels.
+    pub modules: Vec<(String, LevelFilter)>,
+}
+
+fn target(module: &str) -> String {
+    match module {
+        "main" => CRATE.to_string(),
+        module if module.starts_with(CRATE) => module.to_string(),
+        module => format!("{}::{}", CRATE, module),
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::default();
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (module, level) = match item.split_once('=') {
+                Some((module, level)) => (Some(module.trim()), level.trim()),
+                None => (None, item),
+            };
+            let level = LevelFilter::from_str(level)
+                .map_err(|_| format!("unknown log level {:?}", level))?;
+            match module {
+                Some("") => return Err(format!("missing module name in {:?}", item)),
+                Some(module) => filter.modules.push((target(module), level)),
+                None => filter.level = Some(level),
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl LogFilter {
+    pub fn apply(&self, mut logger: SimpleLogger) -> SimpleLogger {
+        for (target, level) in &self.modules {
+            logger = logger.with_module_level(target, *level);
+        }
+        logger
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter() {
+        let filter: LogFilter = "info, sanitize_path=debug,main=warn".parse().unwrap();
+        assert_eq!(filter.level, Some(LevelFilter::Info));
+        assert_eq!(
+            filter.modules,
+            vec![
+                (
+                    "rust_unityextractor::sanitize_path".to_string(),
+                    LevelFilter::Debug
+                ),
+                ("rust_unityextractor".to_string(), LevelFilter::Warn),
+            ]
+        );
+        assert_eq!("".parse::<LogFilter>().unwrap(), LogFilter::default());
+        assert!("main=loud".parse::<LogFilter>().is_err());
+        assert!("=debug".parse::<LogFilter>().is_err());
+    }
+}
@@ -1,43 +1,538 @@
-use std::collections::{HashMap, HashSet};
-use std::ffi::OsString;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::io::Read;
-use std::path::{Path, PathBuf};
+use std::num::NonZeroUsize;
+use std::panic::AssertUnwindSafe;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use argparse::{ArgumentParser, IncrBy, Store};
+use argparse::{ArgumentParser, Collect, IncrBy, List, Store, StoreOption, StoreTrue};
 use flate2::read::GzDecoder;
 use log::{debug, error, info, trace, warn, LevelFilter};
 use simple_logger::SimpleLogger;
 use tokio::io::AsyncWriteExt;
-use tokio::task::JoinHandle;
+use tokio::runtime::Handle;
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore};
 use tokio::{fs, io};
 
+mod baseline;
+mod batch;
+mod checksum;
+mod console;
+mod count;
+mod file_type;
+#[cfg(any(test, feature = "testing"))]
+#[cfg_attr(not(feature = "testing"), allow(dead_code))]
+mod gen_test_package;
+mod immutable;
+mod io_priority;
+mod journal;
+mod log_filter;
+mod meta;
+mod native_plugin;
+mod origin_mark;
+mod output_root;
+mod overwrite;
+mod package_info;
+mod path_rewrite;
+mod picker;
+mod progress;
+mod project_hints;
+mod rate_limit;
+mod rejections;
+mod resolver;
 mod sanitize_path;
+mod sharing;
+mod signature;
+mod size_estimate;
+mod strict;
+mod summary;
+#[cfg(any(test, feature = "testing"))]
+#[cfg_attr(not(test), allow(dead_code))]
+mod testing;
+mod timeline;
+mod trailing_garbage;
+mod transform;
+mod usage;
+mod volumes;
+mod warnings;
+mod watch;
+mod zip_input;
+
+use baseline::{Baseline, Manifest};
+use batch::{Batch, WriteResult, WriteTask};
+use console::StatusLine;
+use file_type::FileType;
+use immutable::ImmutableDir;
+use journal::Journal;
+use log_filter::LogFilter;
+use meta::ImportSettings;
+use output_root::{Mirror, OutputRoot, PendingFile, SyncPolicy};
+use overwrite::OverwritePolicy;
+use path_rewrite::{AsciiPaths, PathCase, Renames};
+use progress::Progress;
+use rate_limit::RateLimit;
+use rejections::RejectionLog;
+use resolver::{PathResolver, PrefixMap};
+use sanitize_path::{RootPolicy, SanitizePolicy};
+use strict::StrictMode;
+use summary::{ExtractedAsset, Summary};
+use timeline::Timeline;
+use trailing_garbage::Tracked;
+
+use transform::{
+    EncodingMode, Eol, NormalizeEncoding, NormalizeLineEndings, TransformChain, TransformRegistry,
+};
+use warnings::ExtractionWarning;
 
 struct Config {
-    input_path: String,
+    input_paths: Vec<String>,
+    output_dir: PathBuf,
+    /// Extract into a folder named after each package's Asset Store title.
+    name_output_dirs: bool,
+    /// `--subdir`: extract into a folder named after each package's file.
+    subdir: bool,
+    /// `--immutable`: extract into read-only folders named after the
+    /// packages' content.
+    immutable: bool,
+    watch_dir: Option<PathBuf>,
     log_level: LevelFilter,
+    log_filter: LogFilter,
+    report_path: Option<PathBuf>,
+    trace_out: Option<PathBuf>,
+    manifest_path: Option<PathBuf>,
+    interactive: bool,
+    usage: bool,
+    /// `--count`: only count what the packages hold.
+    count: bool,
+    /// `--summary`: no per-file output, only failures and the statistics.
+    summary: bool,
+    color: bool,
+    /// `--background`: lower the I/O priority of the whole run.
+    background: bool,
+    /// `--timeout`: how long the whole run may take.
+    timeout: Option<Duration>,
+    options: ExtractOptions,
+}
+
+/// Settings shared by every write task of a run.
+#[derive(Default)]
+struct ExtractOptions {
+    transforms: TransformChain,
+    duplicates: DuplicatePolicy,
+    raw: bool,
+    /// `--no-trailing-garbage`: junk after the last entry makes a package
+    /// corrupt, as any unreadable entry.
+    reject_trailing_garbage: bool,
+    import_settings: bool,
+    bundles: Vec<String>,
+    labels: Vec<String>,
+    status: StatusLine,
+    progress: Progress,
+    strict: StrictMode,
+    sanitize: SanitizePolicy,
+    case: PathCase,
+    ascii: AsciiPaths,
+    /// Moves assets elsewhere before `case` and `ascii` apply.
+    resolver: Option<Box<dyn PathResolver>>,
+    roots: RootPolicy,
+    rejections: RejectionLog,
+    journal: Journal,
+    /// `--queue-depth`: how many write tasks may be pending at once.
+    write_slots: Option<Arc<Semaphore>>,
+    /// `--group-by-folder`: batch small files by their target folder.
+    group_by_folder: bool,
+    sync: SyncPolicy,
+    mark_untrusted: bool,
+    overwrite: OverwritePolicy,
+    /// `--log-interval`: throttles the per-file lines, not the errors.
+    file_lines: RateLimit,
+    /// `--file-timeout`: how long writing a single file may take.
+    file_timeout: Option<Duration>,
+    timeline: Timeline,
+    manifest: Manifest,
+    /// `--baseline`: skip what a previous extraction already wrote.
+    baseline: Option<Baseline>,
+    /// `--guid-db`: pathnames from other packages for assets without one.
+    guid_db: Option<Baseline>,
+    verifying_key: Option<ed25519_dalek::VerifyingKey>,
+    mmap: bool,
+    /// `--keep-folder-content`: save what folder assets unexpectedly hold.
+    keep_folder_content: bool,
+    unknown_entries: UnknownEntries,
+    /// `--concat-ress`: append `asset.resS` to the asset instead of writing
+    /// it next to it.
+    concat_resources: bool,
+    /// `--also-to`: write every file to a second directory too.
+    mirror: Option<Mirror>,
+}
+
+impl ExtractOptions {
+    /// Where a pathname lands below the output directory.
+    fn target_path(&self, path_name: &str) -> Result<String, io::Error> {
+        self.sanitize
+            .sanitize(path_name)
+            .and_then(|path| self.roots.confine(path))
+    }
+
+    /// The pathname after `--case` and `--ascii`.
+    fn rewrite_path_name(&self, path_name: &str) -> String {
+        self.ascii.apply(&self.case.apply(path_name))
+    }
+
+    fn rewrites_path_names(&self) -> bool {
+        self.case != PathCase::Preserve || self.ascii != AsciiPaths::Keep
+    }
+
+    fn has_filters(&self) -> bool {
+        !self.bundles.is_empty() || !self.labels.is_empty()
+    }
+
+    /// Whether an asset passes the `--bundle` and `--label` filters.
+    fn is_selected(&self, settings: Option<&ImportSettings>) -> bool {
+        let Some(settings) = settings else {
+            return !self.has_filters();
+        };
+        let bundle_matches = self.bundles.is_empty()
+            || settings.asset_bundle_name.as_ref().is_some_and(|name| {
+                self.bundles
+                    .iter()
+                    .any(|bundle| bundle.eq_ignore_ascii_case(name))
+            });
+        let label_matches =
+            self.labels.is_empty() || settings.labels.iter().any(|l| self.labels.contains(l));
+        bundle_matches && label_matches
+    }
+}
+
+struct Package {
+    input_path: String,
+    output_dir: PathBuf,
+    /// Pathnames picked with `--interactive`; everything when `None`.
+    selection: Option<picker::Selection>,
+}
+
+#[derive(Default)]
+struct ExtractionStatus {
+    /// `--timeout` cut the run short.
+    timed_out: bool,
+    open_failed: bool,
+    read_error: Option<io::Error>,
+    summary: Summary,
+}
+
+impl ExtractionStatus {
+    /// The most serious problem of the run, input errors first.
+    fn exit_status(&self) -> ExitStatus {
+        if self.timed_out {
+            ExitStatus::TimedOut
+        } else if self.read_error.is_some()
+            || self.summary.unreadable_entries() > 0
+            || self.summary.checksum_mismatches() > 0
+            || self.summary.truncated_entries() > 0
+        {
+            ExitStatus::CorruptArchive
+        } else if self.open_failed {
+            ExitStatus::OpenFailed
+        } else if self.summary.rejected() > 0 {
+            ExitStatus::PathRejected
+        } else if self.summary.failures() > 0 {
+            ExitStatus::PartialFailure
+        } else {
+            ExitStatus::Success
+        }
+    }
+}
+
+/// Process exit codes, documented in the README. Command line errors exit
+/// with 2 as well, like argparse does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExitStatus {
+    Success = 0,
+    OpenFailed = 2,
+    CorruptArchive = 3,
+    PartialFailure = 4,
+    PathRejected = 5,
+    /// A package would replace existing files, see `OverwritePolicy::Refuse`.
+    OverwriteRefused = 6,
+    /// `--timeout`, as timeout(1) exits.
+    TimedOut = 124,
+    Interrupted = 130,
 }
 
+/// How long a run that exceeded `--timeout` gets to stop its writes and
+/// clean up before it exits anyway.
+const TIMEOUT_GRACE: Duration = Duration::from_secs(5);
+
 struct AssetWriteError {
     error: io::Error,
     path: String,
+    /// The archive entry the pathname came from.
+    guid: String,
+    /// The pathname itself was refused by sanitize_path.
+    rejected: bool,
+    /// Never tried, as `--strict` or `--timeout` aborted the run first.
+    skipped: bool,
+}
+
+impl AssetWriteError {
+    fn new(error: io::Error, path: &str, guid: &str) -> AssetWriteError {
+        AssetWriteError {
+            error,
+            path: path.to_string(),
+            guid: guid.to_string(),
+            rejected: false,
+            skipped: false,
+        }
+    }
+
+    fn rejected(error: io::Error, path: &str, guid: &str) -> AssetWriteError {
+        AssetWriteError {
+            rejected: true,
+            ..AssetWriteError::new(error, path, guid)
+        }
+    }
 }
 
 impl fmt::Display for AssetWriteError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}: {}", self.path, self.error)
+        write!(f, "{:?} ({}): {}", self.path, self.guid, self.error)
     }
 }
 
 type AssetMap = HashMap<PathBuf, Vec<u8>>;
-type FolderSet = HashSet<OsString>;
-type ExtractTask = Vec<JoinHandle<Result<(), AssetWriteError>>>;
+type FolderSet = HashSet<PathBuf>;
+type ExtractTask = Vec<WriteTask>;
+
+/// Which occurrence of a GUID found more than once in an archive is kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum DuplicatePolicy {
+    First,
+    #[default]
+    Last,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(DuplicatePolicy::First),
+            "last" => Ok(DuplicatePolicy::Last),
+            _ => Err(format!("unknown duplicate policy {:?}", s)),
+        }
+    }
+}
+
+/// `--unknown-entries`: what to do with entries other than `asset`,
+/// `asset.meta`, `pathname` and `preview.png`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum UnknownEntries {
+    #[default]
+    Skip,
+    /// Unpack them as is below `UNKNOWN_ENTRIES_DIR`.
+    ExtractRaw,
+    /// Fail the package.
+    Error,
+}
+
+impl FromStr for UnknownEntries {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(UnknownEntries::Skip),
+            "extract-raw" => Ok(UnknownEntries::ExtractRaw),
+            "error" => Ok(UnknownEntries::Error),
+            _ => Err(format!("unknown entry policy {:?}", s)),
+        }
+    }
+}
+
+/// Unity skips folders starting with a dot, so it won't import these.
+const UNKNOWN_ENTRIES_DIR: &str = ".unknown-entries";
+
+/// State gathered while walking the entries of one package.
+#[derive(Default)]
+struct ExtractionContext {
+    assets: AssetMap,
+    /// `asset.resS` entries, streamed resource data, by GUID.
+    resources: HashMap<PathBuf, Vec<u8>>,
+    folders: FolderSet,
+    duplicate_guids: HashSet<PathBuf>,
+    meta_guids: HashSet<PathBuf>,
+    import_settings: HashMap<PathBuf, ImportSettings>,
+    resolved_import_settings: Vec<(String, ImportSettings)>,
+    path_names: HashMap<PathBuf, String>,
+    guids_by_path_name: HashMap<String, PathBuf>,
+    digests: HashMap<PathBuf, checksum::Digest>,
+    checksums: Option<HashMap<PathBuf, String>>,
+    warnings: Vec<ExtractionWarning>,
+    /// By GUID, sorted so the report lists warnings in the same order
+    /// from one run to the next.
+    tasks: BTreeMap<PathBuf, WriteTask>,
+    /// Small files waiting to be written, by target folder with
+    /// `--group-by-folder`, all under "" otherwise.
+    batches: BTreeMap<String, Batch>,
+    unreadable_entries: u64,
+    rejected_paths: u64,
+    failed_folders: u64,
+    /// Assets left alone as they match the baseline.
+    unchanged_files: u64,
+    selection: Option<picker::Selection>,
+    /// Pathnames read before their GUID's meta.
+    pending_path_names: BTreeMap<PathBuf, String>,
+    /// The GUID of the last entry read.
+    current_guid: Option<PathBuf>,
+    /// Size of `assets` and `resources`.
+    buffered_bytes: u64,
+    /// Entries short of their header's size, with both sizes.
+    truncated: BTreeMap<PathBuf, (u64, u64)>,
+    renames: Renames,
+}
+
+/// What an `ExtractionContext` holds, to decide when to write early.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ContextStats {
+    /// Asset and resource data waiting for a pathname.
+    buffered_bytes: u64,
+    /// GUIDs whose pathname waits for more of their entries.
+    pending_guids: usize,
+    /// Files queued for writing so far.
+    resolved_files: usize,
+}
+
+/// Past this much buffered data, pathnames that can be resolved are written
+/// without waiting for the end of their GUID.
+const EARLY_FLUSH_BYTES: u64 = 256 * 1024 * 1024;
+
+impl ExtractionContext {
+    fn stats(&self) -> ContextStats {
+        ContextStats {
+            buffered_bytes: self.buffered_bytes,
+            pending_guids: self.pending_path_names.len(),
+            resolved_files: self.tasks.len(),
+        }
+    }
+
+    fn insert_asset(&mut self, path: PathBuf, asset_data: Vec<u8>) {
+        self.buffered_bytes += asset_data.len() as u64;
+        if let Some(replaced) = self.assets.insert(path, asset_data) {
+            self.buffered_bytes -= replaced.len() as u64;
+        }
+    }
+
+    fn take_asset(&mut self, path: &Path) -> Option<Vec<u8>> {
+        let asset_data = self.assets.remove(path)?;
+        self.buffered_bytes -= asset_data.len() as u64;
+        Some(asset_data)
+    }
+
+    fn insert_resource(&mut self, guid: PathBuf, resource: Vec<u8>) {
+        self.buffered_bytes += resource.len() as u64;
+        if let Some(replaced) = self.resources.insert(guid, resource) {
+            self.buffered_bytes -= replaced.len() as u64;
+        }
+    }
+
+    fn take_resource(&mut self, guid: &Path) -> Option<Vec<u8>> {
+        let resource = self.resources.remove(guid)?;
+        self.buffered_bytes -= resource.len() as u64;
+        Some(resource)
+    }
+
+    fn report_duplicate(&mut self, guid: &Path, policy: DuplicatePolicy) {
+        if self.duplicate_guids.insert(guid.to_path_buf()) {
+            let kept = match policy {
+                DuplicatePolicy::First => "first",
+                DuplicatePolicy::Last => "last",
+            };
+            warn!(
+                "GUID {} appears more than once in the archive, keeping the {} one",
+                guid.display(),
+                kept
+            );
+        }
+    }
+}
+
+/// What is left to do once a package has been read.
+#[derive(Default)]
+struct PackageExtraction {
+    tasks: ExtractTask,
+    duplicate_guids: usize,
+    warnings: Vec<ExtractionWarning>,
+    raw_entries: u64,
+    import_settings: Vec<(String, ImportSettings)>,
+    unreadable_entries: u64,
+    rejected_paths: u64,
+    failed_folders: u64,
+    unchanged_files: u64,
+    /// Of the package file, hashed as it was decoded.
+    input_sha256: Option<checksum::Digest>,
+}
 
 fn parse_arguments() -> Config {
     let mut verbose = 0;
     let mut quiet = 0;
-    let mut input_path = String::new();
+    let mut input_paths: Vec<String> = Vec::new();
+    let mut output_dir: Option<PathBuf> = None;
+    let mut also_to: Option<PathBuf> = None;
+    let mut watch_dir: Option<PathBuf> = None;
+    let mut transform_names: Vec<String> = Vec::new();
+    let mut eol = Eol::Keep;
+    let mut encoding = EncodingMode::Keep;
+    let mut duplicates = DuplicatePolicy::Last;
+    let mut unknown_entries = UnknownEntries::Skip;
+    let mut raw = false;
+    let mut reject_trailing_garbage = false;
+    let mut report_path: Option<PathBuf> = None;
+    let mut trace_out: Option<PathBuf> = None;
+    let mut manifest_path: Option<PathBuf> = None;
+    let mut baseline_path: Option<PathBuf> = None;
+    let mut guid_db_path: Option<PathBuf> = None;
+    let mut mmap = false;
+    let mut keep_folder_content = false;
+    let mut concat_resources = false;
+    let mut bundles: Vec<String> = Vec::new();
+    let mut path_maps: Vec<String> = Vec::new();
+    let mut labels: Vec<String> = Vec::new();
+    let mut interactive = false;
+    let mut subdir = false;
+    let mut immutable = false;
+    let mut usage = false;
+    let mut count = false;
+    let mut summary = false;
+    let mut no_color = false;
+    let mut strict = false;
+    let mut resume = false;
+    let mut sanitize = SanitizePolicy::Unity;
+    let mut case = PathCase::Preserve;
+    let mut ascii = AsciiPaths::Keep;
+    let mut confine = false;
+    let mut allowed_roots: Vec<String> = Vec::new();
+    let mut quarantine: Option<String> = None;
+    let mut public_key_path: Option<PathBuf> = None;
+    let mut progress_fd: Option<i32> = None;
+    let mut queue_depth: Option<usize> = None;
+    let mut log_interval: Option<f64> = None;
+    let mut timeout: Option<f64> = None;
+    let mut file_timeout: Option<f64> = None;
+    let mut log_filter: Option<String> = None;
+    let mut group_by_folder = false;
+    let mut sync = SyncPolicy::None;
+    let mut mark_untrusted = false;
+    let mut background = false;
+    let mut force = false;
+    let mut merge = false;
+    let registry = TransformRegistry::with_builtins();
+    let transform_help = format!(
+        "apply a transform to matching assets; one of: {}.",
+        registry.names().collect::<Vec<_>>().join(", ")
+    );
 
     {
         let mut parser = ArgumentParser::new();
@@ -51,186 +546,2870 @@ fn parse_arguments() -> Config {
             .refer(&mut verbose)
             .add_option(&["-v"], IncrBy(1), "increase verbosity; up to 3.");
         parser
-            .refer(&mut input_path)
-            .add_argument("input", Store, "*.unitypackage file")
-            .required();
+            .refer(&mut input_paths)
+            .add_argument("input", List, "*.unitypackage files");
+        parser.refer(&mut output_dir).add_option(
+            &["-o", "--output"],
+            StoreOption,
+            "directory to extract into; defaults to the current directory, or a folder named after the package when its Asset Store metadata is found.",
+        );
+        parser.refer(&mut also_to).add_option(
+            &["--also-to"],
+            StoreOption,
+            "also write every file and folder to this directory, laid out as in OUTPUT, decoding the packages once.",
+        );
+        parser.refer(&mut path_maps).add_option(
+            &["--map-path"],
+            Collect,
+            "FROM=TO: extract what the package has below FROM below TO instead; may be repeated, the longest FROM wins.",
+        );
+        parser.refer(&mut subdir).add_option(
+            &["--subdir"],
+            StoreTrue,
+            "extract each package into OUTPUT/<package file name>.",
+        );
+        parser.refer(&mut immutable).add_option(
+            &["--immutable"],
+            StoreTrue,
+            "extract each package read-only into OUTPUT/Library/PackageCache/<name>@<content hash>, skipping packages already there.",
+        );
+        parser.refer(&mut merge).add_option(
+            &["--merge"],
+            StoreTrue,
+            "extract into an existing project, keeping the files it already has.",
+        );
+        parser.refer(&mut force).add_option(
+            &["--force"],
+            StoreTrue,
+            "extract into an existing project, replacing the files it already has.",
+        );
+        parser.refer(&mut watch_dir).add_option(
+            &["--watch"],
+            StoreOption,
+            "watch a directory, extracting new packages into OUTPUT/<package name>.",
+        );
+        parser
+            .refer(&mut transform_names)
+            .add_option(&["--transform"], Collect, &transform_help);
+        parser.refer(&mut eol).add_option(
+            &["--eol"],
+            Store,
+            "line endings of text assets (scripts, shaders, json, asmdef): lf, crlf or keep (default).",
+        );
+        parser.refer(&mut encoding).add_option(
+            &["--encoding"],
+            Store,
+            "text assets with a BOM or in UTF-16: keep (default), utf8 to re-encode them, or report to only list them.",
+        );
+        parser.refer(&mut duplicates).add_option(
+            &["--duplicates"],
+            Store,
+            "which occurrence of a GUID present more than once wins: first or last (default).",
+        );
+        parser.refer(&mut unknown_entries).add_option(
+            &["--unknown-entries"],
+            Store,
+            "what to do with entries that aren't asset, asset.meta, pathname nor preview.png: skip (default), extract-raw (into .unknown-entries) or error.",
+        );
+        parser.refer(&mut raw).add_option(
+            &["--raw"],
+            StoreTrue,
+            "unpack entries as they are in the archive (<guid>/asset, <guid>/pathname...) without resolving pathnames.",
+        );
+        parser.refer(&mut reject_trailing_garbage).add_option(
+            &["--no-trailing-garbage"],
+            StoreTrue,
+            "treat junk after the last entry of a package as corruption, exit code 3, rather than warn about it.",
+        );
+        parser.refer(&mut report_path).add_option(
+            &["--report"],
+            StoreOption,
+            "write a JSON report of the extraction, including import settings, to this file.",
+        );
+        parser.refer(&mut bundles).add_option(
+            &["--bundle"],
+            Collect,
+            "only extract assets assigned to this asset bundle; may be repeated.",
+        );
+        parser.refer(&mut labels).add_option(
+            &["--label"],
+            Collect,
+            "only extract assets carrying this label; may be repeated.",
+        );
+        parser.refer(&mut interactive).add_option(
+            &["--interactive"],
+            StoreTrue,
+            "browse each package's content and pick the folders and files to extract.",
+        );
+        parser.refer(&mut summary).add_option(
+            &["--summary"],
+            StoreTrue,
+            "only print failed files and the final statistics.",
+        );
+        parser.refer(&mut usage).add_option(
+            &["--usage"],
+            StoreTrue,
+            "don't extract, show the space taken per top-level folder and per extension.",
+        );
+        parser.refer(&mut count).add_option(
+            &["--count"],
+            StoreTrue,
+            "don't extract, only print how many files, folders and bytes the packages hold; faster than --usage.",
+        );
+        parser.refer(&mut strict).add_option(
+            &["--strict"],
+            StoreTrue,
+            "stop at the first rejected pathname or failed write and remove what was extracted.",
+        );
+        parser.refer(&mut resume).add_option(
+            &["--resume"],
+            StoreTrue,
+            "skip the files an interrupted run already extracted, as listed in its journal.",
+        );
+        parser.refer(&mut sanitize).add_option(
+            &["--sanitize"],
+            Store,
+            "pathname checks: strict (refuse absolute paths, .., control characters, reserved names, anything outside Assets and Packages), unity (default) or permissive (drop or replace what can't be written).",
+        );
+        parser.refer(&mut case).add_option(
+            &["--case"],
+            Store,
+            "pathname case: preserve (default) or lower, every folder and file below Assets or Packages; files that then collide get a ~2, ~3... suffix.",
+        );
+        parser.refer(&mut ascii).add_option(
+            &["--ascii"],
+            Store,
+            "characters of pathnames outside ASCII: keep (default), transliterate (é to e) or percent (%C3%A9); the manifest keeps the original pathnames.",
+        );
+        parser.refer(&mut confine).add_option(
+            &["--confine"],
+            StoreTrue,
+            "only write below Assets, Packages and ProjectSettings.",
+        );
+        parser.refer(&mut allowed_roots).add_option(
+            &["--allowed-root"],
+            Collect,
+            "only write below this top level folder, implies --confine; may be repeated.",
+        );
+        parser.refer(&mut quarantine).add_option(
+            &["--quarantine"],
+            StoreOption,
+            "with --confine, move pathnames outside of the allowed roots to this folder instead of refusing them.",
+        );
+        parser.refer(&mut public_key_path).add_option(
+            &["--verify-signature"],
+            StoreOption,
+            "PEM ed25519 public key; only extract packages whose detached <package>.sig signature it verifies.",
+        );
+        parser.refer(&mut no_color).add_option(
+            &["--no-color"],
+            StoreTrue,
+            "don't color the output; NO_COLOR is honored too.",
+        );
+        parser.refer(&mut progress_fd).add_option(
+            &["--progress-fd"],
+            StoreOption,
+            "write progress as JSON lines to this already open file descriptor.",
+        );
+        parser.refer(&mut queue_depth).add_option(
+            &["--queue-depth"],
+            StoreOption,
+            "write at most this many files at once, pausing the archive's decoding meanwhile; unbounded by default.",
+        );
+        parser.refer(&mut log_filter).add_option(
+            &["--log-filter"],
+            StoreOption,
+            "per module log levels, as in \"info,sanitize_path=debug,output_root=warn\"; main is the extraction itself. RUST_LOG is read when not given.",
+        );
+        parser.refer(&mut concat_resources).add_option(
+            &["--concat-ress"],
+            StoreTrue,
+            "append asset.resS resource data to its asset, rather than writing it next to it with a .resS suffix.",
+        );
+        parser.refer(&mut keep_folder_content).add_option(
+            &["--keep-folder-content"],
+            StoreTrue,
+            "save the content some broken exports give folder assets as <folder>.folder-content, rather than dropping it.",
+        );
+        parser.refer(&mut mmap).add_option(
+            &["--mmap"],
+            StoreTrue,
+            "read packages through a memory map rather than read calls; they must not change while extracting.",
+        );
+        parser.refer(&mut trace_out).add_option(
+            &["--trace-out"],
+            StoreOption,
+            "write when each file was decoded, queued and written to this file, for chrome://tracing or Perfetto.",
+        );
+        parser.refer(&mut manifest_path).add_option(
+            &["--manifest"],
+            StoreOption,
+            "write the GUID, pathname and SHA-256 of each asset to this file, to extract a later version with --baseline.",
+        );
+        parser.refer(&mut baseline_path).add_option(
+            &["--baseline"],
+            StoreOption,
+            "only extract assets that changed since this --manifest, over the previous extraction, and list the ones removed.",
+        );
+        parser.refer(&mut guid_db_path).add_option(
+            &["--guid-db"],
+            StoreOption,
+            "a --manifest of other packages or versions, to name assets missing their pathname after the pathname it has for their GUID.",
+        );
+        parser.refer(&mut log_interval).add_option(
+            &["--log-interval"],
+            StoreOption,
+            "print at most one line per extracted file every this many seconds, saying how many were skipped; errors always print.",
+        );
+        parser.refer(&mut timeout).add_option(
+            &["--timeout"],
+            StoreOption,
+            "abort the run after this many seconds, removing partial files, and exit with 124.",
+        );
+        parser.refer(&mut file_timeout).add_option(
+            &["--file-timeout"],
+            StoreOption,
+            "give up on a file that isn't written after this many seconds, counting it as a failure.",
+        );
+        parser.refer(&mut group_by_folder).add_option(
+            &["--group-by-folder"],
+            StoreTrue,
+            "batch small files by target folder, so each folder's files are written together; helps HDDs and network filesystems.",
+        );
+        parser.refer(&mut sync).add_option(
+            &["--sync"],
+            Store,
+            "fsync after each write: none (default), file, dir (the file's folder) or all.",
+        );
+        parser.refer(&mut mark_untrusted).add_option(
+            &["--mark-untrusted"],
+            StoreTrue,
+            "flag extracted files as downloaded from the Internet (com.apple.quarantine on macOS, Zone.Identifier on Windows); they aren't flagged by default.",
+        );
+        parser.refer(&mut background).add_option(
+            &["--background"],
+            StoreTrue,
+            "use idle I/O priority (ionice on Linux, I/O throttling on macOS, background mode on Windows), so a huge package doesn't slow down the rest of the machine.",
+        );
         parser.parse_args_or_exit();
     }
 
+    if input_paths.is_empty() && watch_dir.is_none() {
+        let command = std::env::args().next().unwrap_or_default();
+        eprintln!("{}: an input package or --watch is required", command);
+        std::process::exit(2);
+    }
+    if interactive && (raw || watch_dir.is_some()) {
+        let command = std::env::args().next().unwrap_or_default();
+        eprintln!(
+            "{}: --interactive can't be combined with --raw or --watch",
+            command
+        );
+        std::process::exit(2);
+    }
+
+    if usage && (interactive || raw || watch_dir.is_some()) {
+        let command = std::env::args().next().unwrap_or_default();
+        eprintln!(
+            "{}: --usage can't be combined with --interactive, --raw or --watch",
+            command
+        );
+        std::process::exit(2);
+    }
+    if count && (usage || interactive || raw || watch_dir.is_some()) {
+        let command = std::env::args().next().unwrap_or_default();
+        eprintln!(
+            "{}: --count can't be combined with --usage, --interactive, --raw or --watch",
+            command
+        );
+        std::process::exit(2);
+    }
+
+    if immutable && (subdir || resume || watch_dir.is_some() || baseline_path.is_some()) {
+        eprintln!("--immutable can't be combined with --subdir, --resume, --watch or --baseline");
+        std::process::exit(2);
+    }
+
+    if also_to.is_some() && (immutable || raw || resume || baseline_path.is_some()) {
+        eprintln!("--also-to can't be combined with --immutable, --raw, --resume or --baseline");
+        std::process::exit(2);
+    }
+
+    let overwrite = match (force, merge) {
+        (true, true) => {
+            eprintln!("--force and --merge can't be combined");
+            std::process::exit(2);
+        }
+        (true, false) => OverwritePolicy::Force,
+        (false, true) => OverwritePolicy::Merge,
+        // Updating the previous extraction is the point of a baseline.
+        (false, false) if baseline_path.is_some() => OverwritePolicy::Force,
+        (false, false) => OverwritePolicy::Refuse,
+    };
+    if mark_untrusted && !origin_mark::SUPPORTED {
+        eprintln!("--mark-untrusted is only supported on macOS and Windows");
+        std::process::exit(2);
+    }
+    if background && !io_priority::SUPPORTED {
+        eprintln!("--background is only supported on Linux, macOS and Windows");
+        std::process::exit(2);
+    }
+    if queue_depth == Some(0) {
+        eprintln!("--queue-depth must be at least 1");
+        std::process::exit(2);
+    }
+    if log_interval.is_some_and(|seconds| !(seconds > 0.0 && seconds.is_finite())) {
+        eprintln!("--log-interval must be a positive number of seconds");
+        std::process::exit(2);
+    }
+    for (flag, seconds) in [("--timeout", timeout), ("--file-timeout", file_timeout)] {
+        if seconds.is_some_and(|seconds| !(seconds > 0.0 && seconds.is_finite())) {
+            eprintln!("{} must be a positive number of seconds", flag);
+            std::process::exit(2);
+        }
+    }
+    if timeout.is_some() && watch_dir.is_some() {
+        eprintln!("--timeout can't be combined with --watch");
+        std::process::exit(2);
+    }
+
+    // Re-encoding goes first so later transforms only ever see UTF-8 text.
+    let mut transforms = TransformChain::default();
+    if let Some(transform) = NormalizeEncoding::new(encoding) {
+        transforms.push(Arc::new(transform));
+    }
+    for name in transform_names {
+        match registry.get(&name) {
+            Some(transform) => transforms.push(transform),
+            None => {
+                eprintln!("unknown transform {:?}", name);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(transform) = NormalizeLineEndings::new(eol) {
+        transforms.push(Arc::new(transform));
+    }
+
+    let log_filter = match log_filter {
+        Some(spec) => spec.parse().unwrap_or_else(|e| {
+            eprintln!("invalid --log-filter: {}", e);
+            std::process::exit(2);
+        }),
+        None => match std::env::var("RUST_LOG") {
+            Ok(spec) => spec.parse().unwrap_or_else(|e| {
+                eprintln!("ignoring RUST_LOG: {}", e);
+                LogFilter::default()
+            }),
+            Err(_) => LogFilter::default(),
+        },
+    };
     let log_level = match verbose - quiet {
+        _ if summary => LevelFilter::Error,
         ..=-1 => LevelFilter::Error,
         0 => LevelFilter::Warn,
         1 => LevelFilter::Info,
         2 => LevelFilter::Debug,
         3.. => LevelFilter::Trace,
     };
+    let log_level = match log_filter.level {
+        Some(level) if !summary => level,
+        _ => log_level,
+    };
+    let color = console::use_color(no_color);
+    if confine && allowed_roots.is_empty() {
+        allowed_roots = RootPolicy::DEFAULT_ROOTS
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+    }
+    let quarantine = match quarantine.map(|q| sanitize_path::sanitize_path(&q)) {
+        Some(Ok(q)) if !q.is_empty() => Some(q),
+        Some(_) => {
+            eprintln!("invalid --quarantine folder");
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let verifying_key = public_key_path.map(|path| match signature::load_public_key(&path) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!("cannot read public key {}: {}", path.display(), e);
+            std::process::exit(2);
+        }
+    });
+    let baseline = baseline_path.map(|path| match Baseline::read(&path) {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            eprintln!("cannot read baseline {}: {}", path.display(), e);
+            std::process::exit(2);
+        }
+    });
+    let resolver = match PrefixMap::parse(&path_maps) {
+        Ok(map) if map.is_empty() => None,
+        Ok(map) => Some(Box::new(map) as Box<dyn PathResolver>),
+        Err(e) => {
+            eprintln!("invalid --map-path: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let guid_db = guid_db_path.map(|path| match Baseline::read(&path) {
+        Ok(guid_db) => guid_db,
+        Err(e) => {
+            eprintln!("cannot read GUID database {}: {}", path.display(), e);
+            std::process::exit(2);
+        }
+    });
+    if quarantine.is_some() && allowed_roots.is_empty() {
+        eprintln!("--quarantine requires --confine or --allowed-root");
+        std::process::exit(2);
+    }
+    let progress = match progress_fd.map(Progress::from_fd) {
+        None => Progress::default(),
+        Some(Ok(progress)) => progress,
+        Some(Err(e)) => {
+            eprintln!("cannot use --progress-fd: {}", e);
+            std::process::exit(2);
+        }
+    };
 
+    let name_output_dirs = output_dir.is_none();
+    let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("."));
+    let mirror = also_to.map(|mirror_dir| Mirror {
+        output_dir: output_dir.clone(),
+        mirror_dir,
+    });
     Config {
-        input_path,
+        input_paths,
+        name_output_dirs,
+        subdir,
+        immutable,
+        output_dir,
+        watch_dir,
         log_level,
+        log_filter,
+        options: ExtractOptions {
+            transforms,
+            duplicates,
+            raw,
+            reject_trailing_garbage,
+            import_settings: report_path.is_some(),
+            bundles,
+            labels,
+            // -v runs keep the detailed log lines, -q hides everything but errors.
+            status: if summary {
+                StatusLine::failures_only(color)
+            } else {
+                StatusLine::new(log_level == LevelFilter::Warn, color)
+            },
+            progress,
+            strict: StrictMode::new(strict),
+            journal: Journal::new(resume),
+            write_slots: queue_depth.map(|depth| Arc::new(Semaphore::new(depth))),
+            group_by_folder,
+            sync,
+            mark_untrusted,
+            overwrite,
+            file_lines: RateLimit::new(log_interval.map(Duration::from_secs_f64)),
+            file_timeout: file_timeout.map(Duration::from_secs_f64),
+            timeline: Timeline::new(trace_out.is_some()),
+            manifest: Manifest::new(manifest_path.is_some()),
+            baseline,
+            guid_db,
+            sanitize,
+            case,
+            ascii,
+            resolver,
+            roots: RootPolicy {
+                allowed: allowed_roots,
+                quarantine,
+            },
+            verifying_key,
+            mmap,
+            keep_folder_content,
+            unknown_entries,
+            concat_resources,
+            mirror,
+            ..Default::default()
+        },
+        report_path,
+        trace_out,
+        manifest_path,
+        interactive,
+        usage,
+        count,
+        summary,
+        color,
+        background,
+        timeout: timeout.map(Duration::from_secs_f64),
     }
 }
 
+fn guid_of(path: &Path) -> &Path {
+    path.parent().unwrap_or(path)
+}
+
 fn read_asset_to_memory<R: Read>(
-    assets: &mut AssetMap,
+    context: &mut ExtractionContext,
+    options: &ExtractOptions,
     mut entry: tar::Entry<'_, R>,
     path: PathBuf,
 ) -> Result<(), io::Error> {
+    let guid = guid_of(&path);
+    let duplicate = context.tasks.contains_key(guid) || context.assets.contains_key(&path);
+    if duplicate {
+        context.report_duplicate(guid, options.duplicates);
+        if options.duplicates == DuplicatePolicy::First {
+            return Ok(());
+        }
+    }
+
     debug!("reading asset to memory {:?}", path);
+    let started = Instant::now();
     let mut asset_data = Vec::new();
     entry.read_to_end(&mut asset_data)?;
+    check_length(context, &entry, &path, asset_data.len());
+    let guid_name = guid.to_string_lossy();
+    let entry_name = path.to_string_lossy();
+    options
+        .timeline
+        .record("decode", started, &guid_name, &entry_name);
     trace!(
         "saving {:?} with {} bytes to memory",
         path,
         asset_data.len(),
     );
-    assets.insert(path, asset_data);
+    let digest = checksum::sha256(&asset_data);
+    context.digests.insert(guid.to_path_buf(), digest);
+    context.insert_asset(path, asset_data);
     Ok(())
 }
 
-fn check_for_folders<R: Read>(
-    folders: &mut FolderSet,
+/// Notes an entry that ended before the size in its header, as tar readers
+/// return what there is when the archive itself ends.
+fn check_length<R: Read>(
+    context: &mut ExtractionContext,
+    entry: &tar::Entry<'_, R>,
+    path: &Path,
+    actual: usize,
+) {
+    let expected = entry.size();
+    if actual as u64 >= expected {
+        return;
+    }
+    warn!(
+        "{} is cut short, {} of {} bytes, the archive is truncated",
+        path.display(),
+        actual,
+        expected
+    );
+    context
+        .truncated
+        .insert(path.to_path_buf(), (expected, actual as u64));
+}
+
+fn read_unknown_entry<R: Read>(
+    root: &OutputRoot,
+    options: &ExtractOptions,
     mut entry: tar::Entry<'_, R>,
-    path: PathBuf,
+    path: &Path,
 ) -> Result<(), io::Error> {
-    debug!("reading asset to memory {:?}", path);
-    let mut metadata = String::new();
-    entry.read_to_string(&mut metadata)?;
-    if metadata.contains("folderAsset: yes\n") {
-        folders.insert(path.into_os_string());
+    match options.unknown_entries {
+        UnknownEntries::Skip => trace!("skipping entry with name {}", path.display()),
+        UnknownEntries::ExtractRaw => {
+            let relative_path = Path::new(UNKNOWN_ENTRIES_DIR).join(path);
+            if unpack_entry(root, &mut entry, &relative_path, options)? {
+                info!(
+                    "saved unknown entry {} in {}",
+                    path.display(),
+                    UNKNOWN_ENTRIES_DIR
+                );
+            } else {
+                warn!("refusing to save unknown entry {}", path.display());
+            }
+        }
+        UnknownEntries::Error => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown entry {}", path.display()),
+            ));
+        }
     }
     Ok(())
 }
 
-fn read_destination_path_and_write<R: Read>(
-    assets: &mut AssetMap,
-    folders: &FolderSet,
-    tasks: &mut ExtractTask,
+fn read_resource_to_memory<R: Read>(
+    context: &mut ExtractionContext,
     mut entry: tar::Entry<'_, R>,
     path: PathBuf,
 ) -> Result<(), io::Error> {
-    let mut path_name = String::new();
-    entry.read_to_string(&mut path_name)?;
-
-    let asset_path = path.parent().unwrap().join("asset");
-    if let Some(asset_data) = assets.remove(&asset_path) {
-        tasks.push(tokio::spawn(async move {
-            write_asset_to_pathname(asset_data, path.to_string_lossy().to_string(), path_name).await
-        }));
-    } else {
-        let path_string = path.into_os_string();
-        if folders.contains(&path_string) {
-            warn!("no asset data found for {}", path_name.escape_default());
-        }
-    }
+    debug!("reading resource to memory {:?}", path);
+    let mut resource = Vec::new();
+    entry.read_to_end(&mut resource)?;
+    check_length(context, &entry, &path, resource.len());
+    context.insert_resource(guid_of(&path).to_path_buf(), resource);
     Ok(())
 }
 
-async fn write_asset_to_pathname(
-    asset_data: Vec<u8>,
-    entry_hash: String,
-    path_name: String,
-) -> Result<(), AssetWriteError> {
-    let to_asset_error = |error: io::Error| AssetWriteError {
-        error,
-        path: path_name.clone(),
-    };
-    let target_path = sanitize_path::sanitize_path(&path_name).map_err(to_asset_error)?;
-    let asset_hash: &str;
+fn read_checksums<R: Read>(
+    context: &mut ExtractionContext,
+    mut entry: tar::Entry<'_, R>,
+) -> Result<(), io::Error> {
+    let mut manifest = String::new();
+    entry.read_to_string(&mut manifest)?;
+    let checksums = checksum::parse_manifest(&manifest);
+    debug!("package has checksums for {} assets", checksums.len());
+    context.checksums = Some(checksums);
+    Ok(())
+}
 
-    match entry_hash.find('/') {
-        Some(idx) => {
-            (asset_hash, _) = entry_hash.split_at(idx);
-        }
-        None => {
-            asset_hash = &entry_hash;
-        }
+fn read_metadata<R: Read>(
+    context: &mut ExtractionContext,
+    options: &ExtractOptions,
+    mut entry: tar::Entry<'_, R>,
+    path: PathBuf,
+) -> Result<(), io::Error> {
+    debug!("reading metadata {:?}", path);
+    let mut metadata = String::new();
+    entry.read_to_string(&mut metadata)?;
+    let guid = guid_of(&path).to_path_buf();
+    if meta::is_folder(&metadata) {
+        context.folders.insert(guid.clone());
     }
-
-    if path_name != target_path {
-        debug!("sanitizing path {:?} => {:?}", path_name, target_path);
+    if options.import_settings || options.has_filters() {
+        let settings = meta::import_settings(&metadata);
+        context.import_settings.insert(guid.clone(), settings);
     }
+    context.meta_guids.insert(guid);
+    Ok(())
+}
 
-    if let Some(parent) = Path::new(&target_path).parent() {
-        fs::create_dir_all(parent).await.map_err(to_asset_error)?;
+/// Lets a superseded write land first so the last one wins on disk.
+fn wait_for_superseded(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+    guid: &Path,
+) -> Result<(), io::Error> {
+    let Some(previous) = context.tasks.remove(guid) else {
+        return Ok(());
+    };
+    if matches!(previous, WriteTask::Batched(_)) {
+        flush_batches(context, root, options)?;
+    }
+    match Handle::current().block_on(previous) {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => warn!("failed to write superseded asset: {}", e),
+        Err(e) => warn!(
+            "a superseded extraction task for {} has failed: {}",
+            guid.display(),
+            e
+        ),
     }
-
-    info!("extracting {} to {:?}", asset_hash, target_path);
-    let file = fs::File::create(&target_path)
-        .await
-        .map_err(to_asset_error)?;
-    let mut file_writer = io::BufWriter::new(file);
-    file_writer
-        .write_all(&asset_data)
-        .await
-        .map_err(to_asset_error)?;
-    file_writer.flush().await.map_err(to_asset_error)?;
-    trace!("{} is written to disk", asset_hash);
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = parse_arguments();
-    SimpleLogger::new().with_level(config.log_level).init()?;
-    debug!("opening unitypackage file at {}", &config.input_path);
-    let file = std::fs::File::open(&config.input_path);
+/// Waits for a write slot when `--queue-depth` is set.
+fn acquire_write_slot(options: &ExtractOptions) -> Result<Option<OwnedSemaphorePermit>, io::Error> {
+    let Some(slots) = &options.write_slots else {
+        return Ok(None);
+    };
+    // Holds the archive until a queued write is done.
+    let permit = Handle::current()
+        .block_on(slots.clone().acquire_owned())
+        .map_err(io::Error::other)?;
+    Ok(Some(permit))
+}
 
-    if let Err(err) = file {
-        error!("cannot open file at {}: {}", config.input_path, err);
-        std::process::exit(2);
-    }
+fn aborted_write(path_name: &str, guid_name: &str) -> WriteResult {
+    let error = io::Error::other("extraction aborted");
+    Err(AssetWriteError {
+        skipped: true,
+        ..AssetWriteError::new(error, path_name, guid_name)
+    })
+}
 
-    let decoder = GzDecoder::new(file?);
-    let mut archive = tar::Archive::new(decoder);
-    let mut assets: AssetMap = HashMap::new();
-    let mut folders: FolderSet = HashSet::new();
-    let mut tasks: ExtractTask = Vec::new();
+fn timed_out_write(path_name: &str, guid_name: &str, limit: Duration) -> WriteResult {
+    let message = format!("not written after {:?}", limit);
+    let error = io::Error::new(io::ErrorKind::TimedOut, message);
+    Err(AssetWriteError::new(error, path_name, guid_name))
+}
 
-    debug!("iterating archive's entries");
-    for entry_result in archive.entries()? {
-        let entry = match entry_result {
-            Ok(file) => file,
-            Err(e) => {
-                warn!("error reading entry from archive: {}", e);
-                continue;
-            }
-        };
+fn panicked_write(path_name: &str, guid_name: &str, message: String) -> WriteResult {
+    error!(
+        "writing {} ({}) panicked",
+        path_name.escape_default(),
+        guid_name
+    );
+    let error = io::Error::other(message);
+    Err(AssetWriteError::new(error, path_name, guid_name))
+}
 
-        let path = match entry.path() {
-            Ok(p) => p.to_path_buf(),
-            Err(e) => {
-                warn!("errors reading path from entry: {}", e);
-                continue;
+fn report_write(options: &ExtractOptions, root: &OutputRoot, result: &WriteResult) {
+    match result {
+        Ok(asset) => options.progress.written(&asset.path, asset.size),
+        Err(e) if e.skipped => {}
+        Err(e) => {
+            options.progress.failed(&e.path, &e.guid, &e.error);
+            if e.rejected {
+                let rejections = &options.rejections;
+                rejections.record(root.path(), &e.guid, &e.path, &e.error);
             }
-        };
-
-        if path.ends_with("asset") {
-            read_asset_to_memory(&mut assets, entry, path)?;
+            options.strict.abort(e);
+        }
+    }
+}
+
+fn flush_batches(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+) -> Result<(), io::Error> {
+    let folders: Vec<String> = context.batches.keys().cloned().collect();
+    for folder in folders {
+        flush_batch(context, root, options, &folder)?;
+    }
+    Ok(())
+}
+
+/// Writes the queued small files one after the other in a single blocking
+/// task.
+fn flush_batch(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+    folder: &str,
+) -> Result<(), io::Error> {
+    let Some(batch) = context.batches.remove(folder) else {
+        return Ok(());
+    };
+    let permit = acquire_write_slot(options)?;
+    let files = batch.into_files();
+    let root = root.clone();
+    let options = options.clone();
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        for file in files {
+            let timeline = &options.timeline;
+            timeline.record("queue", file.queued, &file.guid_name, &file.path_name);
+            let started = Instant::now();
+            let result = if options.strict.is_aborted() {
+                aborted_write(&file.path_name, &file.guid_name)
+            } else {
+                let write = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    write_small_asset(
+                        file.asset_data,
+                        &root,
+                        &options,
+                        &file.guid_name,
+                        &file.path_name,
+                    )
+                }));
+                write.unwrap_or_else(|payload| {
+                    let message = batch::panic_message(payload);
+                    panicked_write(&file.path_name, &file.guid_name, message)
+                })
+            };
+            timeline.record("write", started, &file.guid_name, &file.path_name);
+            report_write(&options, &root, &result);
+            // The receiver is gone when reading the package failed midway.
+            let _ = file.sender.send(result);
+        }
+    });
+    Ok(())
+}
+
+fn read_destination_path_and_write<R: Read>(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+    mut entry: tar::Entry<'_, R>,
+    path: PathBuf,
+) -> Result<(), io::Error> {
+    let mut path_name = String::new();
+    entry.read_to_string(&mut path_name)?;
+
+    let guid = guid_of(&path).to_path_buf();
+    if !context.meta_guids.contains(&guid) {
+        // The meta, telling whether this is a folder, may still come.
+        trace!("{}'s pathname arrived before its meta", guid.display());
+        context.pending_path_names.insert(guid, path_name);
+        return Ok(());
+    }
+    write_path_name(context, root, options, guid, path_name)
+}
+
+/// Writes what can be written among the pending pathnames, to release
+/// memory before the end of their GUID.
+fn flush_pending(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+) -> Result<(), io::Error> {
+    let resolvable: Vec<PathBuf> = context
+        .pending_path_names
+        .keys()
+        .filter(|guid| context.assets.contains_key(&guid.join("asset")))
+        .cloned()
+        .collect();
+    if resolvable.is_empty() {
+        return Ok(());
+    }
+    for guid in resolvable {
+        resolve_pending_path_name(context, root, options, &guid)?;
+    }
+    flush_batches(context, root, options)
+}
+
+/// Handles a pathname whose GUID got all its entries, or all it will get.
+fn resolve_pending_path_name(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+    guid: &Path,
+) -> Result<(), io::Error> {
+    match context.pending_path_names.remove(guid) {
+        Some(path_name) => write_path_name(context, root, options, guid.to_path_buf(), path_name),
+        None => Ok(()),
+    }
+}
+
+fn write_path_name(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+    guid: PathBuf,
+    mut path_name: String,
+) -> Result<(), io::Error> {
+    let picked = context
+        .selection
+        .as_ref()
+        .is_none_or(|selection| selection.path_names.contains(&path_name));
+    if !picked || !options.is_selected(context.import_settings.get(&guid)) {
+        trace!(
+            "{} ({}) is filtered out",
+            path_name.escape_default(),
+            guid.display()
+        );
+        context.take_asset(&guid.join("asset"));
+        context.take_resource(&guid);
+        context.import_settings.remove(&guid);
+        return Ok(());
+    }
+
+    if context.tasks.contains_key(&guid) {
+        context.report_duplicate(&guid, options.duplicates);
+        if options.duplicates == DuplicatePolicy::First {
+            return Ok(());
+        }
+    }
+
+    let original_path_name = path_name.clone();
+    let resolved = options
+        .resolver
+        .as_ref()
+        .and_then(|resolver| resolver.resolve(&guid.to_string_lossy(), &path_name));
+    if let Some(resolved) = resolved {
+        debug!(
+            "{} ({}) goes to {}",
+            path_name.escape_default(),
+            guid.display(),
+            resolved.escape_default()
+        );
+        path_name = resolved;
+    }
+    let is_folder = context.folders.contains(&guid) || is_folder_path_name(&path_name);
+    if options.rewrites_path_names() {
+        let rewritten = options.rewrite_path_name(&path_name);
+        let renamed = if is_folder {
+            rewritten.clone()
+        } else {
+            context.renames.claim(&path_name, rewritten.clone())
+        };
+        if renamed != rewritten {
+            warn!(
+                "{} ({}) is rewritten like another file's, writing it to {}",
+                path_name.escape_default(),
+                guid.display(),
+                renamed.escape_default()
+            );
+            context.warnings.push(ExtractionWarning::PathSanitized {
+                path_name: path_name.clone(),
+                sanitized: renamed.clone(),
+            });
+        }
+        path_name = renamed;
+    }
+
+    let asset_path = guid.join("asset");
+    let mut asset_data = context.take_asset(&asset_path);
+    if is_folder {
+        create_folder(context, root, options, &guid, &path_name);
+        let Some(content) = asset_data.take().filter(|data| !data.is_empty()) else {
+            return Ok(());
+        };
+        // Seen in broken exports, Unity itself never writes any.
+        warn!(
+            "folder {} ({}) has {} bytes of content, {}",
+            path_name.escape_default(),
+            guid.display(),
+            content.len(),
+            if options.keep_folder_content {
+                "saving them next to it"
+            } else {
+                "dropping them"
+            }
+        );
+        context.warnings.push(ExtractionWarning::FolderContent {
+            path_name: path_name.clone(),
+            guid: guid.to_string_lossy().to_string(),
+            size: content.len() as u64,
+        });
+        if !options.keep_folder_content {
+            return Ok(());
+        }
+        path_name = suffixed_path_name(&path_name, ".folder-content");
+        asset_data = Some(content);
+    }
+    if let Some(mut asset_data) = asset_data {
+        if let Some(digest) = context.digests.get(&guid).copied() {
+            let guid_name = guid.to_string_lossy();
+            let manifest = &options.manifest;
+            manifest.record(&guid_name, &path_name, &original_path_name, &digest);
+            let unchanged = options.baseline.as_ref().is_some_and(|baseline| {
+                baseline.is_unchanged(&guid_name, &path_name, &digest)
+                    && !context.resources.contains_key(&guid)
+                    && options
+                        .target_path(&path_name)
+                        .is_ok_and(|target_path| root.path().join(target_path).is_file())
+            });
+            if unchanged {
+                trace!(
+                    "{} ({}) is unchanged since the baseline",
+                    path_name.escape_default(),
+                    guid.display()
+                );
+                if let Some(baseline) = &options.baseline {
+                    options.manifest.keep(&guid_name, baseline);
+                }
+                context.import_settings.remove(&guid);
+                context.unchanged_files += 1;
+                return Ok(());
+            }
+            let modified = options.baseline.as_ref().is_some_and(|baseline| {
+                options
+                    .target_path(&path_name)
+                    .and_then(|target_path| std::fs::read(root.path().join(target_path)))
+                    .is_ok_and(|data| baseline.is_modified(&guid_name, &path_name, &data))
+            });
+            if modified {
+                warn!(
+                    "{} ({}) was edited since the baseline, keeping it",
+                    path_name.escape_default(),
+                    guid.display()
+                );
+                if let Some(baseline) = &options.baseline {
+                    options.manifest.keep(&guid_name, baseline);
+                }
+                context.warnings.push(ExtractionWarning::ModifiedLocally {
+                    path_name: path_name.clone(),
+                    guid: guid_name.to_string(),
+                });
+                context.take_resource(&guid);
+                context.import_settings.remove(&guid);
+                return Ok(());
+            }
+        }
+        wait_for_superseded(context, root, options, &guid)?;
+        context.path_names.insert(guid.clone(), path_name.clone());
+        let first = context
+            .guids_by_path_name
+            .insert(path_name.clone(), guid.clone());
+        if let Some(first_guid) = first.filter(|first_guid| *first_guid != guid) {
+            warn!(
+                "{} and {} both write to {}",
+                first_guid.display(),
+                guid.display(),
+                path_name.escape_default()
+            );
+            context.warnings.push(ExtractionWarning::DuplicatePathname {
+                path_name: path_name.clone(),
+                first_guid: first_guid.to_string_lossy().to_string(),
+                guid: guid.to_string_lossy().to_string(),
+            });
+            wait_for_superseded(context, root, options, &first_guid)?;
+        }
+        if let Some(settings) = context.import_settings.remove(&guid) {
+            if options.import_settings {
+                let resolved = (path_name.clone(), settings);
+                context.resolved_import_settings.push(resolved);
+            }
+        }
+        let guid_name = guid.to_string_lossy().to_string();
+        if let Some(resource) = context.take_resource(&guid) {
+            if options.concat_resources {
+                asset_data.extend(resource);
+            } else {
+                let resource_key = guid.join("asset.resS");
+                wait_for_superseded(context, root, options, &resource_key)?;
+                let resource_path_name = suffixed_path_name(&path_name, ".resS");
+                let resource_guid = guid_name.clone();
+                queue_write(
+                    context,
+                    root,
+                    options,
+                    resource_key,
+                    resource_guid,
+                    resource_path_name,
+                    resource,
+                )?;
+            }
+        }
+        queue_write(
+            context, root, options, guid, guid_name, path_name, asset_data,
+        )?;
+    } else {
+        warn!(
+            "no asset data found for {} ({})",
+            path_name.escape_default(),
+            guid.display()
+        );
+    }
+    Ok(())
+}
+
+/// Queues the write of a file, in a batch when it is small, under
+/// `task_key` in the context's tasks.
+fn queue_write(
+    context: &mut ExtractionContext,
+    root: &Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+    task_key: PathBuf,
+    guid_name: String,
+    path_name: String,
+    asset_data: Vec<u8>,
+) -> Result<(), io::Error> {
+    let target_path = options.target_path(&path_name).ok();
+    if let Some(target_path) = &target_path {
+        if options.journal.is_done(root.path(), target_path) {
+            return Ok(());
+        }
+        let merge = options.overwrite == OverwritePolicy::Merge;
+        if merge && root.path().join(target_path).exists() {
+            debug!("keeping the existing {} ({})", target_path, guid_name);
+            return Ok(());
+        }
+    }
+    // Batches are written in one blocking call, which can't be given up on
+    // file by file.
+    if asset_data.len() <= batch::SMALL_FILE_SIZE && options.file_timeout.is_none() {
+        let folder = match (options.group_by_folder, &target_path) {
+            (true, Some(target_path)) => target_path
+                .rsplit_once('/')
+                .map(|(folder, _)| folder.to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+        let batch = context.batches.entry(folder.clone()).or_default();
+        let task = batch.push(asset_data, path_name, guid_name);
+        let full = batch.is_full();
+        context.tasks.insert(task_key, task);
+        if full {
+            flush_batch(context, root, options, &folder)?;
+        }
+        return Ok(());
+    }
+    let queued = Instant::now();
+    let permit = acquire_write_slot(options)?;
+    let root = root.clone();
+    let options = options.clone();
+    let task = tokio::spawn(async move {
+        let _permit = permit;
+        let timeline = &options.timeline;
+        timeline.record("queue", queued, &guid_name, &path_name);
+        let started = Instant::now();
+        if options.strict.is_aborted() {
+            return aborted_write(&path_name, &guid_name);
+        }
+        // A panicking write is reported as a failure of that file.
+        let write = tokio::spawn({
+            let root = root.clone();
+            let options = options.clone();
+            let path_name = path_name.clone();
+            let guid_name = guid_name.clone();
+            async move {
+                write_asset_to_pathname(asset_data, root, &options, &guid_name, &path_name).await
+            }
+        });
+        let joined = match options.file_timeout {
+            Some(limit) => {
+                let abort = write.abort_handle();
+                let joined = tokio::time::timeout(limit, write).await;
+                if joined.is_err() {
+                    // Dropping the write removes its pending file.
+                    abort.abort();
+                }
+                joined.map_err(|_| limit)
+            }
+            None => Ok(write.await),
+        };
+        let result = match joined {
+            Err(limit) => timed_out_write(&path_name, &guid_name, limit),
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => {
+                let message = match e.try_into_panic() {
+                    Ok(payload) => batch::panic_message(payload),
+                    Err(e) => e.to_string(),
+                };
+                panicked_write(&path_name, &guid_name, message)
+            }
+        };
+        timeline.record("write", started, &guid_name, &path_name);
+        report_write(&options, &root, &result);
+        result
+    });
+    context.tasks.insert(task_key, WriteTask::Spawned(task));
+    Ok(())
+}
+
+fn create_folder(
+    context: &mut ExtractionContext,
+    root: &OutputRoot,
+    options: &ExtractOptions,
+    guid: &Path,
+    path_name: &str,
+) {
+    let guid_name = guid.to_string_lossy();
+    if let Err(e) = create_folder_structure(root, options, &guid_name, path_name) {
+        warn!("cannot create folder {}", e);
+        options.strict.abort(&e);
+        if e.rejected {
+            let rejections = &options.rejections;
+            rejections.record(root.path(), &guid_name, path_name, &e.error);
+            context.rejected_paths += 1;
+        } else {
+            context.failed_folders += 1;
+        }
+    }
+}
+
+/// A sibling of a pathname, `Assets/Foo` with `.resS` giving `Assets/Foo.resS`.
+fn suffixed_path_name(path_name: &str, suffix: &str) -> String {
+    let folder = path_name
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('\0')
+        .trim_end_matches(['/', '\\']);
+    format!("{}{}", folder, suffix)
+}
+
+/// Some exporters omit the folder's meta but end its pathname with a slash.
+fn is_folder_path_name(path_name: &str) -> bool {
+    path_name
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('\0')
+        .ends_with(['/', '\\'])
+}
+
+fn create_folder_structure(
+    root: &OutputRoot,
+    options: &ExtractOptions,
+    guid: &str,
+    path_name: &str,
+) -> Result<(), AssetWriteError> {
+    let folder = options
+        .target_path(path_name)
+        .map_err(|e| AssetWriteError::rejected(e, path_name, guid))?;
+
+    debug!("creating folder {:?} ({})", root.path().join(&folder), guid);
+    root.create_dir_all(Path::new(&folder))
+        .map_err(|error| AssetWriteError::new(error, path_name, guid))?;
+    if let Some(mirror) = root.mirror() {
+        mirror
+            .create_dir_all(Path::new(&folder))
+            .map_err(|error| AssetWriteError::new(in_mirror(mirror, error), path_name, guid))?;
+    }
+    Ok(())
+}
+
+/// Tells errors of the `--also-to` copy apart.
+fn in_mirror(mirror: &OutputRoot, error: io::Error) -> io::Error {
+    let message = format!("in {}: {}", mirror.path().display(), error);
+    io::Error::new(error.kind(), message)
+}
+
+/// An asset ready to be written, with what was learned from its content.
+struct PreparedAsset {
+    relative_path: String,
+    asset_data: Vec<u8>,
+    guid: String,
+    file_type: FileType,
+    native_plugin: Option<native_plugin::NativePlugin>,
+    project_hints: Vec<project_hints::Hint>,
+    warnings: Vec<ExtractionWarning>,
+}
+
+impl PreparedAsset {
+    fn written(
+        self,
+        root: &OutputRoot,
+        options: &ExtractOptions,
+        started: Instant,
+    ) -> ExtractedAsset {
+        let write_time = started.elapsed();
+        let size = self.asset_data.len() as u64;
+        options
+            .journal
+            .record(root.path(), &self.relative_path, size);
+        trace!("{} is written to disk", self.guid);
+        ExtractedAsset {
+            path: self.relative_path,
+            size,
+            file_type: self.file_type,
+            native_plugin: self.native_plugin,
+            project_hints: self.project_hints,
+            warnings: self.warnings,
+            write_time,
+        }
+    }
+}
+
+fn prepare_asset(
+    asset_data: Vec<u8>,
+    root: &OutputRoot,
+    options: &ExtractOptions,
+    guid: &str,
+    path_name: &str,
+) -> Result<PreparedAsset, AssetWriteError> {
+    let target_path = options
+        .target_path(path_name)
+        .map_err(|e| AssetWriteError::rejected(e, path_name, guid))?;
+
+    let mut warnings = Vec::new();
+    if path_name != target_path {
+        debug!(
+            "sanitizing path {:?} => {:?} ({})",
+            path_name, target_path, guid
+        );
+        // Old Unity versions end pathnames with "\n00", that's expected.
+        let visible = path_name
+            .split(['\0', '\n', '\r'])
+            .next()
+            .unwrap_or_default();
+        if visible != target_path {
+            warnings.push(ExtractionWarning::PathSanitized {
+                path_name: path_name.to_string(),
+                sanitized: target_path.clone(),
+            });
+        }
+    }
+
+    let relative_path = target_path;
+    let target_path = root.path().join(&relative_path);
+
+    let file_type = FileType::detect(&asset_data);
+    let native_plugin = native_plugin::inspect(&target_path, &asset_data);
+    let project_hints = project_hints::scan(&target_path, &asset_data);
+    let asset_data = options.transforms.apply(&target_path, asset_data);
+    options
+        .manifest
+        .record_written(guid, path_name, &asset_data);
+    if log::log_enabled!(log::Level::Info) {
+        if let Some(held_back) = options.file_lines.check() {
+            let held_back = match held_back {
+                0 => String::new(),
+                n => format!(" (+{} more)", n),
+            };
+            info!(
+                "extracting {} ({}) to {:?}{}",
+                guid, file_type, target_path, held_back
+            );
+        }
+    }
+    Ok(PreparedAsset {
+        relative_path,
+        asset_data,
+        guid: guid.to_string(),
+        file_type,
+        native_plugin,
+        project_hints,
+        warnings,
+    })
+}
+
+fn create_pending_file(
+    root: &OutputRoot,
+    relative_path: &Path,
+    mark_untrusted: bool,
+) -> Result<(std::fs::File, PendingFile), io::Error> {
+    let (file, pending) = root.create_pending(relative_path)?;
+    if mark_untrusted {
+        let path = root.pending_path(&pending);
+        if let Err(e) = origin_mark::mark_untrusted(&file, &path) {
+            drop(file);
+            root.discard(&pending);
+            return Err(e);
+        }
+    }
+    Ok((file, pending))
+}
+
+/// Writes an entry as it is in the tar stream, through a pending file as
+/// the assets are. `false` when its path would leave `root`.
+fn unpack_entry<R: Read>(
+    root: &OutputRoot,
+    entry: &mut tar::Entry<'_, R>,
+    relative_path: &Path,
+    options: &ExtractOptions,
+) -> Result<bool, io::Error> {
+    let confined = relative_path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !confined {
+        return Ok(false);
+    }
+    if entry.header().entry_type().is_dir() {
+        root.create_dir_all(relative_path)?;
+        return Ok(true);
+    }
+    let new = options.strict.is_new(root, relative_path);
+    let (mut file, pending) = create_pending_file(root, relative_path, options.mark_untrusted)?;
+    let mut written = std::io::copy(entry, &mut file).map(|_| ());
+    if written.is_ok() && options.sync.syncs_files() {
+        written = file.sync_all();
+    }
+    drop(file);
+    if let Err(e) = written.and_then(|()| root.persist(&pending)) {
+        root.discard(&pending);
+        return Err(e);
+    }
+    if new {
+        options.strict.record(root, relative_path);
+    }
+    if options.sync.syncs_folders() {
+        root.sync_parent(relative_path)?;
+    }
+    Ok(true)
+}
+
+/// Writes the `--also-to` copy of a file, the same way as the original.
+fn write_mirror(
+    mirror: &OutputRoot,
+    options: &ExtractOptions,
+    relative_path: &Path,
+    asset_data: &[u8],
+) -> Result<(), io::Error> {
+    use std::io::Write;
+
+    let new = options.strict.is_new(mirror, relative_path);
+    let (mut file, pending) = create_pending_file(mirror, relative_path, options.mark_untrusted)?;
+    let mut written = file.write_all(asset_data);
+    if written.is_ok() && options.sync.syncs_files() {
+        written = file.sync_all();
+    }
+    drop(file);
+    if let Err(e) = written.and_then(|()| mirror.persist(&pending)) {
+        mirror.discard(&pending);
+        return Err(e);
+    }
+    if new {
+        options.strict.record(mirror, relative_path);
+    }
+    if options.sync.syncs_folders() {
+        mirror.sync_parent(relative_path)?;
+    }
+    Ok(())
+}
+
+/// Removes the pending file of a write that failed or was given up on, the
+/// write's future being dropped mid-way by `--file-timeout`.
+struct DiscardOnDrop {
+    root: Arc<OutputRoot>,
+    pending: PendingFile,
+    armed: bool,
+}
+
+impl DiscardOnDrop {
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for DiscardOnDrop {
+    fn drop(&mut self) {
+        // Right away: a blocking task spawned from here may never run when
+        // the runtime is already shutting down.
+        if self.armed {
+            self.root.discard(&self.pending);
+        }
+    }
+}
+
+async fn write_asset_to_pathname(
+    asset_data: Vec<u8>,
+    root: Arc<OutputRoot>,
+    options: &Arc<ExtractOptions>,
+    guid: &str,
+    path_name: &str,
+) -> Result<ExtractedAsset, AssetWriteError> {
+    let to_asset_error = |error: io::Error| AssetWriteError::new(error, path_name, guid);
+    let asset = prepare_asset(asset_data, &root, options, guid, path_name)?;
+    let started = Instant::now();
+    // Made along with the file, so a write given up on while it's created
+    // doesn't leave it behind either.
+    let (file, discard, new) = {
+        let root = root.clone();
+        let relative_path = PathBuf::from(&asset.relative_path);
+        let options = options.clone();
+        tokio::task::spawn_blocking(move || {
+            let new = options.strict.is_new(&root, &relative_path);
+            let (file, pending) =
+                create_pending_file(&root, &relative_path, options.mark_untrusted)?;
+            let discard = DiscardOnDrop {
+                root,
+                pending,
+                armed: true,
+            };
+            Ok::<_, io::Error>((file, discard, new))
+        })
+        .await
+        .map_err(|e| to_asset_error(io::Error::other(e)))?
+        .map_err(to_asset_error)?
+    };
+    let pending = &discard.pending;
+    let written = async {
+        let mut file_writer = io::BufWriter::new(fs::File::from_std(file));
+        file_writer.write_all(&asset.asset_data).await?;
+        file_writer.flush().await?;
+        if options.sync.syncs_files() {
+            file_writer.get_ref().sync_all().await?;
+        }
+        // Windows refuses to rename a file that is still open.
+        drop(file_writer);
+        let root = root.clone();
+        let pending = pending.clone();
+        tokio::task::spawn_blocking(move || root.persist(&pending))
+            .await
+            .map_err(io::Error::other)?
+    };
+    written.await.map_err(to_asset_error)?;
+    discard.disarm();
+    if new {
+        options
+            .strict
+            .record(&root, Path::new(&asset.relative_path));
+    }
+    if options.sync.syncs_folders() {
+        let root = root.clone();
+        let relative_path = PathBuf::from(&asset.relative_path);
+        tokio::task::spawn_blocking(move || root.sync_parent(&relative_path))
+            .await
+            .map_err(|e| to_asset_error(io::Error::other(e)))?
+            .map_err(to_asset_error)?;
+    }
+    let asset = if root.mirror().is_some() {
+        let root = root.clone();
+        let options = options.clone();
+        let (asset, mirrored) = tokio::task::spawn_blocking(move || {
+            let mirror = root.mirror().expect("mirror");
+            let relative_path = Path::new(&asset.relative_path);
+            let data = &asset.asset_data;
+            let mirrored = write_mirror(mirror, &options, relative_path, data)
+                .map_err(|e| in_mirror(mirror, e));
+            (asset, mirrored)
+        })
+        .await
+        .map_err(|e| to_asset_error(io::Error::other(e)))?;
+        mirrored.map_err(to_asset_error)?;
+        asset
+    } else {
+        asset
+    };
+    Ok(asset.written(&root, options, started))
+}
+
+/// The blocking counterpart of `write_asset_to_pathname`, for batches.
+fn write_small_asset(
+    asset_data: Vec<u8>,
+    root: &OutputRoot,
+    options: &ExtractOptions,
+    guid: &str,
+    path_name: &str,
+) -> Result<ExtractedAsset, AssetWriteError> {
+    use std::io::Write;
+
+    let to_asset_error = |error: io::Error| AssetWriteError::new(error, path_name, guid);
+    let asset = prepare_asset(asset_data, root, options, guid, path_name)?;
+    let started = Instant::now();
+    let relative_path = Path::new(&asset.relative_path);
+    let new = options.strict.is_new(root, relative_path);
+    let (mut file, pending) =
+        create_pending_file(root, relative_path, options.mark_untrusted).map_err(to_asset_error)?;
+    let mut written = file.write_all(&asset.asset_data);
+    if written.is_ok() && options.sync.syncs_files() {
+        written = file.sync_all();
+    }
+    // Windows refuses to rename a file that is still open.
+    drop(file);
+    let written = written.and_then(|()| root.persist(&pending));
+    if let Err(e) = written {
+        root.discard(&pending);
+        return Err(to_asset_error(e));
+    }
+    if new {
+        options.strict.record(root, relative_path);
+    }
+    if options.sync.syncs_folders() {
+        let relative_path = Path::new(&asset.relative_path);
+        root.sync_parent(relative_path).map_err(to_asset_error)?;
+    }
+    if let Some(mirror) = root.mirror() {
+        write_mirror(mirror, options, relative_path, &asset.asset_data)
+            .map_err(|e| to_asset_error(in_mirror(mirror, e)))?;
+    }
+    Ok(asset.written(root, options, started))
+}
+
+fn extract_package<R: Read>(
+    input_path: &str,
+    reader: R,
+    output_dir: &Path,
+    options: &Arc<ExtractOptions>,
+    selection: Option<picker::Selection>,
+) -> Result<PackageExtraction, io::Error> {
+    let decoder = GzDecoder::new(reader);
+    let mut archive = tar::Archive::new(Tracked::new(decoder));
+    let mut root = OutputRoot::open(output_dir)?;
+    if let Some(mirror) = &options.mirror {
+        root = root.with_mirror(&mirror.dir_for(output_dir))?;
+    }
+    let root = Arc::new(root);
+    let mut context = ExtractionContext {
+        selection,
+        ..Default::default()
+    };
+    let mut next_header = None;
+    let mut entry_error = false;
+
+    debug!("iterating {}'s entries", input_path);
+    for entry_result in archive.entries()? {
+        if options.strict.is_aborted() {
+            break;
+        }
+        let entry = match entry_result {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("error reading entry from archive: {}", e);
+                context.unreadable_entries += 1;
+                entry_error = true;
+                continue;
+            }
+        };
+        next_header = Some(trailing_garbage::next_header(&entry));
+
+        let path = match entry.path() {
+            Ok(p) => p.to_path_buf(),
+            Err(e) => {
+                warn!("errors reading path from entry: {}", e);
+                continue;
+            }
+        };
+
+        // Unity writes the entries of a GUID together: once another GUID
+        // starts, whatever the previous one was waiting for isn't coming.
+        // Unless it has no asset yet, as when all pathnames come first.
+        let guid = guid_of(&path);
+        if context.current_guid.as_deref() != Some(guid) {
+            if let Some(previous) = context.current_guid.replace(guid.to_path_buf()) {
+                let has_asset = context.assets.contains_key(&previous.join("asset"))
+                    || context.folders.contains(&previous);
+                if has_asset {
+                    resolve_pending_path_name(&mut context, &root, options, &previous)?;
+                }
+            }
+        }
+
+        // The tar reader skips the data of entries left unread, through a
+        // small buffer, rather than holding it in memory.
+        let unselected = context
+            .selection
+            .as_ref()
+            .is_some_and(|selection| !selection.guids.contains(guid));
+        let entry_type = entry.header().entry_type();
+        if unselected && (path.ends_with("asset") || path.ends_with("asset.resS")) {
+            trace!("skipping unselected {}", path.display());
+        } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+            warn!("refusing to extract link {}", path.display());
+        } else if entry_type.is_dir() {
+            if path.ends_with("asset") {
+                debug!("{} is a directory, treating it as a folder", path.display());
+                context.folders.insert(guid_of(&path).to_path_buf());
+            } else {
+                trace!("skipping folder {}", path.display());
+            }
+        } else if !entry_type.is_file() && !entry_type.is_gnu_sparse() {
+            warn!(
+                "skipping {} with unsupported entry type {:?}",
+                path.display(),
+                entry_type
+            );
+        } else if path.ends_with("asset") {
+            read_asset_to_memory(&mut context, options, entry, path)?;
+        } else if path.ends_with("asset.resS") {
+            read_resource_to_memory(&mut context, entry, path)?;
         } else if path.ends_with("asset.meta") {
-            check_for_folders(&mut folders, entry, path)?;
+            read_metadata(&mut context, options, entry, path)?;
         } else if path.ends_with("pathname") {
-            read_destination_path_and_write(&mut assets, &folders, &mut tasks, entry, path)?;
-        } else if path.ends_with("/") {
-            trace!("skipping folder {}", path.display());
+            read_destination_path_and_write(&mut context, &root, options, entry, path)?;
+        } else if path == Path::new("checksums") {
+            read_checksums(&mut context, entry)?;
+        } else if path.ends_with("preview.png") {
+            trace!("skipping preview {}", path.display())
         } else {
-            trace!("skipping entry with name {}", path.display())
+            read_unknown_entry(&root, options, entry, &path)?;
+        }
+        if context.stats().buffered_bytes > EARLY_FLUSH_BYTES {
+            flush_pending(&mut context, &root, options)?;
+        }
+    }
+
+    if entry_error {
+        if let Some(warning) = trailing_garbage(archive, next_header, input_path, options) {
+            context.unreadable_entries -= 1;
+            context.warnings.push(warning);
+        }
+    }
+
+    let pending: Vec<PathBuf> = context.pending_path_names.keys().cloned().collect();
+    for guid in pending {
+        resolve_pending_path_name(&mut context, &root, options, &guid)?;
+    }
+    if let Some(guid_db) = &options.guid_db {
+        let orphans: Vec<PathBuf> = context
+            .assets
+            .keys()
+            .map(|a| guid_of(a).to_path_buf())
+            .collect();
+        for guid in orphans {
+            let guid_name = guid.to_string_lossy().to_string();
+            let Some(path_name) = guid_db.path_name(&guid_name) else {
+                continue;
+            };
+            info!(
+                "naming {} after the GUID database: {}",
+                guid_name,
+                path_name.escape_default()
+            );
+            context.warnings.push(ExtractionWarning::RecoveredPathName {
+                guid: guid_name.clone(),
+                path_name: path_name.to_string(),
+            });
+            write_path_name(&mut context, &root, options, guid, path_name.to_string())?;
+        }
+    }
+    flush_batches(&mut context, &root, options)?;
+    debug!("end of archive {}: {:?}", input_path, context.stats());
+    for (asset_path, asset_data) in context.assets {
+        let guid = guid_of(&asset_path);
+        let file_type = FileType::detect(&asset_data);
+        context.warnings.push(ExtractionWarning::OrphanAsset {
+            guid: guid.to_string_lossy().to_string(),
+            file_type,
+        });
+        match file_type.extension() {
+            Some(extension) => warn!(
+                "no pathname found for {}, it looks like a .{} file ({})",
+                guid.display(),
+                extension,
+                file_type
+            ),
+            None => warn!("no pathname found for {} ({})", guid.display(), file_type),
+        }
+    }
+    for guid in context.resources.keys() {
+        warn!("no pathname found for {}'s asset.resS", guid.display());
+    }
+    for (entry, (expected, actual)) in &context.truncated {
+        let guid = guid_of(entry);
+        let path_name = context.path_names.get(guid).map(|path_name| {
+            if entry.ends_with("asset.resS") && !options.concat_resources {
+                suffixed_path_name(path_name, ".resS")
+            } else {
+                path_name.clone()
+            }
+        });
+        context.warnings.push(ExtractionWarning::TruncatedEntry {
+            entry: entry.to_string_lossy().to_string(),
+            path_name,
+            expected: *expected,
+            actual: *actual,
+        });
+    }
+    if let Some(checksums) = &context.checksums {
+        for (guid, expected) in checksums {
+            let unselected = context
+                .selection
+                .as_ref()
+                .is_some_and(|selection| !selection.guids.contains(guid));
+            if unselected {
+                continue;
+            }
+            let path_name = context.path_names.get(guid).cloned();
+            match context.digests.get(guid) {
+                Some(digest) if checksum::to_hex(digest) == *expected => {}
+                Some(_) => {
+                    warn!(
+                        "checksum mismatch for {}, the package is damaged",
+                        guid.display()
+                    );
+                    context.warnings.push(ExtractionWarning::ChecksumMismatch {
+                        guid: guid.to_string_lossy().to_string(),
+                        path_name,
+                    });
+                }
+                None => warn!("{} has a checksum but no asset", guid.display()),
+            }
+        }
+    }
+    let missing_meta = context
+        .tasks
+        .keys()
+        .filter(|guid| !context.meta_guids.contains(*guid))
+        .filter_map(|guid| context.path_names.get(guid).cloned())
+        .map(|path_name| ExtractionWarning::MissingMeta { path_name });
+    context.warnings.extend(missing_meta);
+    Ok(PackageExtraction {
+        tasks: context.tasks.into_values().collect(),
+        duplicate_guids: context.duplicate_guids.len(),
+        warnings: context.warnings,
+        import_settings: context.resolved_import_settings,
+        unreadable_entries: context.unreadable_entries,
+        rejected_paths: context.rejected_paths,
+        failed_folders: context.failed_folders,
+        unchanged_files: context.unchanged_files,
+        ..Default::default()
+    })
+}
+
+fn extract_raw<R: Read>(
+    input_path: &str,
+    reader: R,
+    output_dir: &Path,
+    options: &ExtractOptions,
+) -> Result<PackageExtraction, io::Error> {
+    let mut archive = tar::Archive::new(Tracked::new(GzDecoder::new(reader)));
+    let root = OutputRoot::open(output_dir)?;
+    let mut extraction = PackageExtraction::default();
+    let mut next_header = None;
+    let mut entry_error = false;
+
+    debug!("unpacking {}'s entries as is", input_path);
+    for entry_result in archive.entries()? {
+        if options.strict.is_aborted() {
+            break;
+        }
+        let mut entry = match entry_result {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("error reading entry from archive: {}", e);
+                extraction.unreadable_entries += 1;
+                entry_error = true;
+                continue;
+            }
+        };
+        next_header = Some(trailing_garbage::next_header(&entry));
+
+        let path = match entry.path() {
+            Ok(p) => p.to_path_buf(),
+            Err(e) => {
+                warn!("errors reading path from entry: {}", e);
+                extraction.unreadable_entries += 1;
+                continue;
+            }
+        };
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            warn!("refusing to extract link {}", path.display());
+            continue;
+        }
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            warn!("skipping {} of type {:?}", path.display(), entry_type);
+            continue;
+        }
+
+        if unpack_entry(&root, &mut entry, &path, options)? {
+            trace!("unpacked {}", path.display());
+            extraction.raw_entries += 1;
+        } else {
+            warn!(
+                "refusing to unpack {} outside of the output directory",
+                path.display()
+            );
+            let reason = format!("{} is outside of the output directory", path.display());
+            let guid = path.iter().next().unwrap_or_default().to_string_lossy();
+            let entry_name = path.to_string_lossy();
+            options
+                .rejections
+                .record(output_dir, &guid, &entry_name, &reason);
+            options.strict.abort(reason);
+            extraction.rejected_paths += 1;
+        }
+    }
+
+    if entry_error {
+        if let Some(warning) = trailing_garbage(archive, next_header, input_path, options) {
+            extraction.unreadable_entries -= 1;
+            extraction.warnings.push(warning);
+        }
+    }
+    Ok(extraction)
+}
+
+/// A warning instead of an unreadable entry when `archive` ended on one
+/// followed by nothing but junk, unless `--no-trailing-garbage`.
+fn trailing_garbage<R: Read>(
+    archive: tar::Archive<Tracked<GzDecoder<R>>>,
+    next_header: Option<u64>,
+    input_path: &str,
+    options: &ExtractOptions,
+) -> Option<ExtractionWarning> {
+    // Junk where the first header should be is no package at all.
+    let next_header = next_header.filter(|_| !options.reject_trailing_garbage)?;
+    match trailing_garbage::garbage_length(archive.into_inner(), next_header) {
+        Ok(Some(bytes)) => {
+            warn!(
+                "ignoring {} bytes of junk after the last entry of {}",
+                bytes, input_path
+            );
+            Some(ExtractionWarning::TrailingGarbage { bytes })
+        }
+        Ok(None) => None,
+        Err(e) => {
+            debug!("cannot read what follows in {}: {}", input_path, e);
+            None
+        }
+    }
+}
+
+async fn extract_packages(
+    packages: Vec<Package>,
+    options: &Arc<ExtractOptions>,
+) -> Result<ExtractionStatus, AcquireError> {
+    // Decoding is CPU bound, so run at most one archive per core at a time.
+    let max_decoders = std::thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1);
+    let decoder_slots = Arc::new(Semaphore::new(max_decoders));
+    let mut decoders = Vec::new();
+    let mut status = ExtractionStatus::default();
+
+    for package in packages {
+        let permit = decoder_slots.clone().acquire_owned().await?;
+        if options.strict.is_aborted() {
+            break;
+        }
+        let input_path = package.input_path;
+        debug!("opening unitypackage file at {}", &input_path);
+        let mut file = match volumes::Volumes::open(&input_path) {
+            Ok(file) => file,
+            Err(err) => {
+                error!("cannot open file at {}: {}", input_path, err);
+                status.open_failed = true;
+                continue;
+            }
+        };
+        if options.mmap {
+            if let Err(e) = file.map() {
+                warn!("cannot map {}, reading it instead: {}", input_path, e);
+            }
+        }
+
+        info!("extracting package {}", input_path);
+        let output_dir = package.output_dir;
+        let selection = package.selection;
+        let options = options.clone();
+        decoders.push(tokio::task::spawn_blocking(move || {
+            let mut file = file;
+            if let Some(size) = size_estimate::estimate(&mut file) {
+                info!("{} expands to at least {} bytes", input_path, size);
+                options.progress.add_total(size);
+            }
+            let verified = match &options.verifying_key {
+                Some(key) => signature::verify_package(&input_path, &mut file, key),
+                None => Ok(()),
+            };
+            let mut reader = checksum::HashingReader::new(file);
+            let result = if let Err(e) = verified {
+                Err(e)
+            } else if options.raw {
+                extract_raw(&input_path, &mut reader, &output_dir, &options)
+            } else {
+                extract_package(&input_path, &mut reader, &output_dir, &options, selection)
+            };
+            let result = result.map(|mut package| {
+                match reader.finish() {
+                    Ok(digest) => package.input_sha256 = Some(digest),
+                    Err(e) => warn!("cannot checksum {}: {}", input_path, e),
+                }
+                package
+            });
+            drop(permit);
+            (input_path, result)
+        }));
+    }
+
+    let mut tasks: ExtractTask = Vec::new();
+    for decoder in decoders {
+        match decoder.await {
+            Ok((input_path, Ok(package))) => {
+                if let Some(digest) = package.input_sha256 {
+                    info!("{} has SHA-256 {}", input_path, checksum::to_hex(&digest));
+                    options.manifest.record_input(&input_path, &digest);
+                    status.summary.add_input(input_path, digest);
+                }
+                tasks.extend(package.tasks);
+                status.summary.add_duplicate_guids(package.duplicate_guids);
+                status.summary.add_warnings(package.warnings);
+                status.summary.add_raw_entries(package.raw_entries);
+                status.summary.add_import_settings(package.import_settings);
+                status
+                    .summary
+                    .add_unreadable_entries(package.unreadable_entries);
+                status.summary.add_rejected(package.rejected_paths);
+                status.summary.add_failures(package.failed_folders);
+                status.summary.add_unchanged(package.unchanged_files);
+            }
+            Ok((input_path, Err(e))) => {
+                error!("failed to read package {}: {}", input_path, e);
+                status.read_error.get_or_insert(e);
+            }
+            Err(e) => {
+                error!("a package decoder has failed: {}", e);
+                status.read_error.get_or_insert(e.into());
+            }
         }
     }
 
-    debug!("end of archive");
     for task in tasks {
         match task.await {
-            Ok(Ok(())) => {}
+            Ok(Ok(asset)) => {
+                if options.status.shows_extracted() {
+                    if let Some(held_back) = options.file_lines.check() {
+                        options.status.extracted(&asset.path, held_back);
+                    }
+                }
+                status.summary.add(asset);
+            }
+            Ok(Err(e)) if e.skipped => {
+                debug!("skipped {} after the abort", e.path.escape_default());
+            }
             Ok(Err(e)) => {
-                warn!("failed to write asset: {}", e);
+                options
+                    .status
+                    .failed(&e.path, format_args!("{}: {}", e.guid, e.error));
+                if e.rejected {
+                    status.summary.add_rejected(1);
+                } else {
+                    status.summary.add_failures(1);
+                }
             }
             Err(e) => {
                 warn!("an extraction task has failed: {}", e);
+                status.summary.add_failures(1);
+            }
+        }
+    }
+
+    status.summary.add_sharing_retries(sharing::take_retries());
+    if options.strict.is_aborted() {
+        options.strict.clean_up();
+    }
+    if let Some(baseline) = &options.baseline {
+        status.summary.add_removed(baseline.removed());
+    }
+    options.rejections.write_reports();
+    warnings::log_repeated();
+    let incomplete = status.read_error.is_some() || status.summary.failures() > 0;
+    options.journal.finish(incomplete);
+    Ok(status)
+}
+
+fn show_usage(
+    input_paths: Vec<String>,
+    policy: SanitizePolicy,
+    report_path: Option<PathBuf>,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let mut disk_usage = usage::DiskUsage::default();
+    let mut status = ExitStatus::Success;
+    for input_path in input_paths {
+        let index = volumes::Volumes::open(&input_path).and_then(picker::index_package);
+        match index {
+            Ok(index) => disk_usage.add_package(index, policy),
+            Err(e) => {
+                error!("cannot read package {}: {}", input_path, e);
+                status = ExitStatus::OpenFailed;
+            }
+        }
+    }
+    disk_usage.print()?;
+    if let Some(report_path) = report_path {
+        let report =
+            serde_json::to_vec_pretty(&serde_json::json!({ "usage": disk_usage.to_json() }))?;
+        if let Err(e) = std::fs::write(&report_path, report) {
+            error!("cannot write report to {}: {}", report_path.display(), e);
+        }
+    }
+    Ok(status)
+}
+
+fn show_count(
+    input_paths: Vec<String>,
+    report_path: Option<PathBuf>,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let mut total = count::PackageCount::default();
+    let mut packages = Vec::new();
+    let mut status = ExitStatus::Success;
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout().lock();
+    for input_path in &input_paths {
+        match volumes::Volumes::open(input_path).and_then(count::count_package) {
+            Ok(package) => {
+                writeln!(
+                    stdout,
+                    "{}: {} files, {} folders, {} bytes",
+                    input_path, package.files, package.folders, package.bytes
+                )?;
+                total.add(package);
+                let mut package = package.to_json();
+                package["path"] = input_path.as_str().into();
+                packages.push(package);
+            }
+            Err(e) => {
+                error!("cannot read package {}: {}", input_path, e);
+                status = ExitStatus::OpenFailed;
             }
         }
     }
+    if input_paths.len() > 1 {
+        writeln!(
+            stdout,
+            "total: {} files, {} folders, {} bytes",
+            total.files, total.folders, total.bytes
+        )?;
+    }
+    if let Some(report_path) = report_path {
+        let mut count = total.to_json();
+        count["packages"] = packages.into();
+        let report = serde_json::to_vec_pretty(&serde_json::json!({ "count": count }))?;
+        if let Err(e) = std::fs::write(&report_path, report) {
+            error!("cannot write report to {}: {}", report_path.display(), e);
+        }
+    }
+    Ok(status)
+}
+
+async fn run(config: Config) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let options = Arc::new(config.options);
+
+    if let Some(watch_dir) = config.watch_dir {
+        watch::watch_directory(&watch_dir, &config.output_dir, options).await?;
+        return Ok(ExitStatus::Success);
+    }
+
+    if config.usage {
+        return show_usage(config.input_paths, options.sanitize, config.report_path);
+    }
+    if config.count {
+        return show_count(config.input_paths, config.report_path);
+    }
+
+    let mut packages = Vec::new();
+    let mut package_infos = Vec::new();
+    let mut immutable_dirs = Vec::new();
+    let mut refused = false;
+    let mut overwrite_refused = false;
+    for input_path in config.input_paths {
+        let selection = if config.interactive {
+            match picker::choose(&input_path, options.sanitize) {
+                Ok(Some(selection)) => Some(selection),
+                Ok(None) => {
+                    info!("skipping package {}", input_path);
+                    continue;
+                }
+                Err(e) => {
+                    error!("cannot browse {}: {}", input_path, e);
+                    return Ok(ExitStatus::OpenFailed);
+                }
+            }
+        } else {
+            None
+        };
+        let info = package_info::read_sidecar(&input_path);
+        let output_dir = match &info {
+            _ if config.immutable => {
+                let name = match &info {
+                    Some(info) => info.folder_name(),
+                    None => package_info::file_name(&input_path),
+                };
+                let immutable = ImmutableDir::for_package(&config.output_dir, &input_path, &name)
+                    .and_then(|dir| {
+                        if !dir.exists() {
+                            dir.clear_partial()?;
+                        }
+                        Ok(dir)
+                    });
+                match immutable {
+                    Ok(dir) if dir.exists() => {
+                        info!("{} is already in {}", input_path, dir.path.display());
+                        continue;
+                    }
+                    Ok(dir) => {
+                        let partial_path = dir.partial_path.clone();
+                        immutable_dirs.push(dir);
+                        partial_path
+                    }
+                    Err(e) => {
+                        error!("cannot prepare {} for --immutable: {}", input_path, e);
+                        refused = true;
+                        continue;
+                    }
+                }
+            }
+            _ if config.subdir => config.output_dir.join(package_info::file_name(&input_path)),
+            Some(info) if config.name_output_dirs => config.output_dir.join(info.folder_name()),
+            _ => config.output_dir.clone(),
+        };
+        if !config.immutable {
+            // Reads the whole package when the output has files already.
+            let refuses = tokio::task::spawn_blocking({
+                let (input_path, output_dir) = (input_path.clone(), output_dir.clone());
+                let options = options.clone();
+                move || overwrite::refuses(&input_path, &output_dir, &options)
+            })
+            .await?;
+            if refuses {
+                overwrite_refused = true;
+                continue;
+            }
+        }
+        if let Some(info) = info {
+            package_infos.push(info.to_json(&input_path));
+        }
+        packages.push(Package {
+            input_path,
+            output_dir,
+            selection,
+        });
+    }
+    let extraction = extract_packages(packages, &options);
+    let mut stuck = false;
+    let mut status = match config.timeout {
+        Some(limit) => {
+            tokio::pin!(extraction);
+            tokio::select! {
+                status = &mut extraction => status?,
+                () = tokio::time::sleep(limit) => {
+                    options.strict.stop(format!("--timeout of {:?} exceeded", limit));
+                    // Queued writes are skipped and decoders stop, but a
+                    // write stuck on a network filesystem may never return.
+                    let mut status = match tokio::time::timeout(TIMEOUT_GRACE, extraction).await {
+                        Ok(status) => status?,
+                        Err(_) => {
+                            error!("writes still pending after {:?}, exiting anyway", TIMEOUT_GRACE);
+                            let removed = output_root::discard_all_pending();
+                            debug!("removed {} partially written files", removed);
+                            stuck = true;
+                            ExtractionStatus::default()
+                        }
+                    };
+                    status.timed_out = true;
+                    status
+                }
+            }
+        }
+        None => extraction.await?,
+    };
+    for dir in immutable_dirs {
+        if status.exit_status() != ExitStatus::Success {
+            warn!(
+                "not sealing {} as the extraction failed",
+                dir.partial_path.display()
+            );
+        } else if let Err(e) = dir.seal() {
+            error!("cannot move {} in place: {}", dir.path.display(), e);
+            refused = true;
+        }
+    }
+    status.summary.add_package_infos(package_infos);
+    if config.summary {
+        status.summary.print();
+    } else {
+        status.summary.log();
+    }
+    options.progress.finish();
+    if let Some(report_path) = config.report_path {
+        let report = serde_json::to_vec_pretty(&status.summary.to_json())?;
+        if let Err(e) = std::fs::write(&report_path, report) {
+            error!("cannot write report to {}: {}", report_path.display(), e);
+        }
+    }
+    if let Some(trace_out) = config.trace_out {
+        if let Err(e) = options.timeline.write(&trace_out) {
+            error!("cannot write trace to {}: {}", trace_out.display(), e);
+        }
+    }
+    if let Some(manifest_path) = config.manifest_path {
+        if let Err(e) = options.manifest.write(&manifest_path) {
+            error!(
+                "cannot write manifest to {}: {}",
+                manifest_path.display(),
+                e
+            );
+        }
+    }
     info!("done");
+    if stuck {
+        // Shutting the runtime down would wait for the stuck writes.
+        std::process::exit(ExitStatus::TimedOut as i32);
+    }
 
-    Ok(())
+    if refused && status.exit_status() == ExitStatus::Success {
+        return Ok(ExitStatus::OpenFailed);
+    }
+    if overwrite_refused && status.exit_status() == ExitStatus::Success {
+        return Ok(ExitStatus::OverwriteRefused);
+    }
+    Ok(status.exit_status())
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    #[cfg(feature = "testing")]
+    if std::env::args().nth(1).as_deref() == Some(gen_test_package::COMMAND) {
+        let mut args: Vec<String> = std::env::args().collect();
+        args.remove(0);
+        return Ok(tokio::task::block_in_place(|| gen_test_package::run(args)));
+    }
+    let config = parse_arguments();
+    let logger = SimpleLogger::new()
+        .with_level(config.log_level)
+        .with_colors(config.color);
+    config.log_filter.apply(logger).init()?;
+    // Before any write, and before the blocking pool starts its threads.
+    if config.background {
+        if let Err(e) = io_priority::lower() {
+            warn!("cannot lower the I/O priority: {}", e);
+        }
+    }
+
+    tokio::select! {
+        status = run(config) => Ok(ExitCode::from(status? as u8)),
+        Ok(()) = tokio::signal::ctrl_c() => {
+            warn!("interrupted");
+            // Exit right away, the runtime would wait for busy decoders.
+            std::process::exit(ExitStatus::Interrupted as i32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use testing::{EntryOrder, TestUnityPackageBuilder};
+
+    async fn extract(package: Vec<u8>) -> tempfile::TempDir {
+        extract_with(package, ExtractOptions::default()).await
+    }
+
+    async fn extract_with(package: Vec<u8>, options: ExtractOptions) -> tempfile::TempDir {
+        let output = tempfile::tempdir().unwrap();
+        let output_dir = output.path().to_path_buf();
+        let options = Arc::new(options);
+        let extraction = tokio::task::spawn_blocking(move || {
+            extract_package("test", Cursor::new(package), &output_dir, &options, None)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        for task in extraction.tasks {
+            assert!(task.await.unwrap().is_ok());
+        }
+        output
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_folders() {
+        let package = TestUnityPackageBuilder::new()
+            .add_folder("f1", "Assets/WithMeta")
+            .add_entry("f2/pathname", b"Assets/NoMeta/")
+            .add_entry("f3/pathname", b"Assets\\Windows\\\n00")
+            .add_asset("a1", "Assets/WithMeta/file.txt", b"data")
+            .build();
+        let output = extract(package).await;
+
+        assert!(output.path().join("Assets/WithMeta").is_dir());
+        assert!(output.path().join("Assets/NoMeta").is_dir());
+        assert!(output.path().join("Assets/Windows").is_dir());
+        assert_eq!(
+            std::fs::read(output.path().join("Assets/WithMeta/file.txt")).unwrap(),
+            b"data"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_entry_types() {
+        let package = TestUnityPackageBuilder::new()
+            .add_directory("d1/asset")
+            .add_entry("d1/pathname", b"Assets/DirectoryAsset")
+            .add_symlink("s1/asset", "/etc/passwd")
+            .add_entry("s1/pathname", b"Assets/Symlink.txt")
+            .add_hard_link("h1/asset", "s1/asset")
+            .add_entry("h1/pathname", b"Assets/HardLink.txt")
+            .build();
+        let output = extract(package).await;
+
+        assert!(output.path().join("Assets/DirectoryAsset").is_dir());
+        assert!(!output.path().join("Assets/Symlink.txt").exists());
+        assert!(!output.path().join("Assets/HardLink.txt").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bundle_and_label_filters() {
+        let meta = |bundle: &str, label: &str| {
+            format!(
+                "labels:\n- {}\nTextureImporter:\n  assetBundleName: {}\n",
+                label, bundle
+            )
+        };
+        let package = || {
+            TestUnityPackageBuilder::new()
+                .add_asset_with_meta("a1", "Assets/rock.png", b"1", &meta("rocks", "Nature"))
+                .add_asset_with_meta("a2", "Assets/tree.png", b"2", &meta("trees", "Nature"))
+                .add_asset_with_meta("a3", "Assets/car.png", b"3", &meta("rocks", "City"))
+                .add_asset("a4", "Assets/plain.png", b"4")
+                .build()
+        };
+
+        let options = ExtractOptions {
+            bundles: vec!["Rocks".to_string()],
+            ..Default::default()
+        };
+        let output = extract_with(package(), options).await;
+        assert!(output.path().join("Assets/rock.png").exists());
+        assert!(!output.path().join("Assets/tree.png").exists());
+        assert!(output.path().join("Assets/car.png").exists());
+        assert!(!output.path().join("Assets/plain.png").exists());
+
+        let options = ExtractOptions {
+            bundles: vec!["rocks".to_string()],
+            labels: vec!["Nature".to_string()],
+            ..Default::default()
+        };
+        let output = extract_with(package(), options).await;
+        assert!(output.path().join("Assets/rock.png").exists());
+        assert!(!output.path().join("Assets/tree.png").exists());
+        assert!(!output.path().join("Assets/car.png").exists());
+    }
+
+    async fn extract_warnings(package: Vec<u8>) -> (tempfile::TempDir, Vec<ExtractionWarning>) {
+        let output = tempfile::tempdir().unwrap();
+        let output_dir = output.path().to_path_buf();
+        let extraction = tokio::task::spawn_blocking(move || {
+            let options = Arc::new(ExtractOptions::default());
+            extract_package("test", Cursor::new(package), &output_dir, &options, None)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        for task in extraction.tasks {
+            assert!(task.await.unwrap().is_ok());
+        }
+        (output, extraction.warnings)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_warnings() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"first")
+            .add_asset("a2", "Assets/a.txt", b"second")
+            .add_entry("a3/asset", b"orphan")
+            .add_entry("a4/asset", b"no meta")
+            .add_entry("a4/pathname", b"Assets\\b.txt")
+            .build();
+        let (output, warnings) = extract_warnings(package).await;
+
+        assert!(warnings.contains(&ExtractionWarning::DuplicatePathname {
+            path_name: "Assets/a.txt".to_string(),
+            first_guid: "a1".to_string(),
+            guid: "a2".to_string(),
+        }));
+        assert!(warnings.contains(&ExtractionWarning::OrphanAsset {
+            guid: "a3".to_string(),
+            file_type: FileType::Text,
+        }));
+        assert!(warnings.contains(&ExtractionWarning::MissingMeta {
+            path_name: "Assets\\b.txt".to_string(),
+        }));
+        assert_eq!(
+            std::fs::read(output.path().join("Assets/a.txt")).unwrap(),
+            b"second"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_folder_content() {
+        let package = TestUnityPackageBuilder::new()
+            .add_entry("f1/asset", b"stray")
+            .add_folder("f1", "Assets/Rocks")
+            .add_asset("a1", "Assets/Rocks/a.txt", b"a")
+            .add_entry("f2/asset", b"")
+            .add_folder("f2", "Assets/Empty")
+            .build();
+        let (output, warnings) = extract_warnings(package.clone()).await;
+        assert_eq!(
+            warnings,
+            vec![ExtractionWarning::FolderContent {
+                path_name: "Assets/Rocks".to_string(),
+                guid: "f1".to_string(),
+                size: 5,
+            }]
+        );
+        assert!(output.path().join("Assets/Rocks/a.txt").is_file());
+        assert!(output.path().join("Assets/Empty").is_dir());
+        assert!(!output.path().join("Assets/Rocks.folder-content").exists());
+
+        let options = ExtractOptions {
+            keep_folder_content: true,
+            ..Default::default()
+        };
+        let output = extract_with(package, options).await;
+        let content = output.path().join("Assets/Rocks.folder-content");
+        assert_eq!(std::fs::read(content).unwrap(), b"stray");
+        assert!(output.path().join("Assets/Rocks/a.txt").is_file());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unknown_entries() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"a")
+            .add_entry("a1/preview.png", b"preview")
+            .add_entry("a1/metaData", b"forensics")
+            .build();
+        let output = extract_with(package.clone(), ExtractOptions::default()).await;
+        assert!(!output.path().join(UNKNOWN_ENTRIES_DIR).exists());
+
+        let options = ExtractOptions {
+            unknown_entries: UnknownEntries::ExtractRaw,
+            ..Default::default()
+        };
+        let output = extract_with(package.clone(), options).await;
+        let unknown = output.path().join(UNKNOWN_ENTRIES_DIR);
+        assert_eq!(
+            std::fs::read(unknown.join("a1/metaData")).unwrap(),
+            b"forensics"
+        );
+        assert!(!unknown.join("a1/preview.png").exists());
+        assert!(output.path().join("Assets/a.txt").is_file());
+
+        let output = tempfile::tempdir().unwrap();
+        let output_dir = output.path().to_path_buf();
+        let options = Arc::new(ExtractOptions {
+            unknown_entries: UnknownEntries::Error,
+            ..Default::default()
+        });
+        let result = tokio::task::spawn_blocking(move || {
+            extract_package("test", Cursor::new(package), &output_dir, &options, None)
+        })
+        .await
+        .unwrap();
+        assert!(result.is_err());
+    }
+
+    /// A small mutation fuzzer over the whole extraction, damaging either
+    /// the package or the tar stream inside it: whatever the bytes, it
+    /// returns without panicking and writes nothing outside the output
+    /// directory.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_mutated_packages() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"data")
+            .add_folder("f1", "Assets/Folder")
+            .add_asset("a2", "Assets/Folder/b.bin", &[7; 600])
+            .build();
+        let mut tar = Vec::new();
+        GzDecoder::new(package.as_slice())
+            .read_to_end(&mut tar)
+            .unwrap();
+        let parent = tempfile::tempdir().unwrap();
+        let options = Arc::new(ExtractOptions::default());
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for round in 0..200 {
+            let mut mutated = if round % 2 == 0 {
+                package.clone()
+            } else {
+                tar.clone()
+            };
+            for _ in 0..1 + next() % 8 {
+                let at = next() as usize % mutated.len();
+                mutated[at] = next() as u8;
+            }
+            mutated.truncate(next() as usize % (mutated.len() + 1));
+            if round % 2 == 1 {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+                std::io::Write::write_all(&mut encoder, &mutated).unwrap();
+                mutated = encoder.finish().unwrap();
+            }
+
+            let output_dir = parent.path().join("out");
+            let extraction = tokio::task::spawn_blocking({
+                let (output_dir, options) = (output_dir.clone(), options.clone());
+                move || extract_package("fuzz", Cursor::new(mutated), &output_dir, &options, None)
+            })
+            .await
+            .unwrap();
+            if let Ok(extraction) = extraction {
+                for task in extraction.tasks {
+                    let _ = task.await.unwrap();
+                }
+            }
+            let entries: Vec<_> = std::fs::read_dir(parent.path()).unwrap().collect();
+            assert!(entries.len() <= 1, "round {} wrote {:?}", round, entries);
+            let _ = std::fs::remove_dir_all(&output_dir);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resources() {
+        let package = TestUnityPackageBuilder::new()
+            .add_entry("t1/asset", b"texture")
+            .add_entry("t1/asset.resS", b"pixels")
+            .add_entry("t1/pathname", b"Assets/Tex.asset\n00")
+            .build();
+        let output = extract(package.clone()).await;
+        let read = |path: &str| std::fs::read(output.path().join(path)).unwrap();
+        assert_eq!(read("Assets/Tex.asset"), b"texture");
+        assert_eq!(read("Assets/Tex.asset.resS"), b"pixels");
+
+        let options = ExtractOptions {
+            concat_resources: true,
+            ..Default::default()
+        };
+        let output = extract_with(package, options).await;
+        let tex = std::fs::read(output.path().join("Assets/Tex.asset")).unwrap();
+        assert_eq!(tex, b"texturepixels");
+        assert!(!output.path().join("Assets/Tex.asset.resS").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_meta_after_pathname() {
+        // Each GUID's pathname, then its meta, then its asset.
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/Rocks/a.txt", b"a")
+            .add_entry("f1/asset", b"")
+            .add_folder("f1", "Assets/Rocks")
+            .add_folder("f2", "Assets/Empty")
+            .order(EntryOrder::Reversed)
+            .build();
+        let (output, warnings) = extract_warnings(package).await;
+        assert!(warnings.is_empty());
+        assert_eq!(
+            std::fs::read(output.path().join("Assets/Rocks/a.txt")).unwrap(),
+            b"a"
+        );
+        assert!(output.path().join("Assets/Empty").is_dir());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pathnames_first() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"a")
+            .add_asset("a2", "Assets/b.txt", b"b")
+            .add_asset("a3", "Assets/c.txt", b"c")
+            .order(EntryOrder::PathnamesFirst)
+            .build();
+        let (output, warnings) = extract_warnings(package).await;
+        assert!(warnings.is_empty());
+        for (name, data) in [("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c")] {
+            let path = output.path().join("Assets").join(name);
+            assert_eq!(std::fs::read(path).unwrap(), data);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_context_stats() {
+        let output = tempfile::tempdir().unwrap();
+        let root = Arc::new(OutputRoot::open(output.path()).unwrap());
+        let options = Arc::new(ExtractOptions::default());
+        let mut context = ExtractionContext::default();
+        context.insert_asset(PathBuf::from("a1/asset"), b"first".to_vec());
+        context.insert_asset(PathBuf::from("a1/asset"), b"last".to_vec());
+        context.insert_resource(PathBuf::from("a1"), b"res".to_vec());
+        context.insert_asset(PathBuf::from("a2/asset"), b"orphan".to_vec());
+        let pending = |guid: &str, path_name: &str| (PathBuf::from(guid), path_name.to_string());
+        context
+            .pending_path_names
+            .extend([pending("a1", "Assets/a.bin"), pending("a3", "Assets/c.bin")]);
+        let expected = ContextStats {
+            buffered_bytes: 13,
+            pending_guids: 2,
+            resolved_files: 0,
+        };
+        assert_eq!(context.stats(), expected);
+
+        tokio::task::block_in_place(|| flush_pending(&mut context, &root, &options)).unwrap();
+        let expected = ContextStats {
+            buffered_bytes: 6,
+            pending_guids: 1,
+            resolved_files: 2,
+        };
+        assert_eq!(context.stats(), expected);
+        for (_, task) in context.tasks {
+            assert!(task.await.unwrap().is_ok());
+        }
+        assert_eq!(
+            std::fs::read(output.path().join("Assets/a.bin.resS")).unwrap(),
+            b"res"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_checksums() {
+        let manifest = format!(
+            "{}  a1/asset\n{}  a2/asset\n",
+            checksum::to_hex(&checksum::sha256(b"good")),
+            checksum::to_hex(&checksum::sha256(b"expected")),
+        );
+        let package = TestUnityPackageBuilder::new()
+            .add_entry("checksums", manifest.as_bytes())
+            .add_asset("a1", "Assets/a.txt", b"good")
+            .add_asset("a2", "Assets/b.txt", b"damaged")
+            .build();
+        let (_, warnings) = extract_warnings(package).await;
+        assert_eq!(
+            warnings,
+            vec![ExtractionWarning::ChecksumMismatch {
+                guid: "a2".to_string(),
+                path_name: Some("Assets/b.txt".to_string()),
+            }]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_truncated_entry() {
+        let package = TestUnityPackageBuilder::new()
+            .order(EntryOrder::PathnamesFirst)
+            .add_large_asset("a1", "Assets/a.bin", 4 << 20)
+            .build_short(3 << 20);
+        // The meta entry came after the end.
+        let (output, warnings) = extract_warnings(package).await;
+        let [ExtractionWarning::TruncatedEntry {
+            entry,
+            path_name,
+            expected,
+            actual,
+        }, ExtractionWarning::MissingMeta { .. }] = warnings.as_slice()
+        else {
+            panic!("expected a truncated entry, got {:?}", warnings);
+        };
+        assert_eq!(entry, "a1/asset");
+        assert_eq!(path_name.as_deref(), Some("Assets/a.bin"));
+        assert_eq!(*expected, 4 << 20);
+        assert!(*actual > 0 && *actual < *expected);
+        let written = std::fs::metadata(output.path().join("Assets/a.bin")).unwrap();
+        assert_eq!(written.len(), *actual);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unselected_data() {
+        let manifest = format!(
+            "{}  a2/asset\n",
+            checksum::to_hex(&checksum::sha256(b"skipped"))
+        );
+        let package = TestUnityPackageBuilder::new()
+            .add_entry("checksums", manifest.as_bytes())
+            .add_asset("a1", "Assets/a.txt", b"picked")
+            .add_large_asset("a2", "Assets/b.bin", 3 << 20)
+            .build();
+        let output = tempfile::tempdir().unwrap();
+        let output_dir = output.path().to_path_buf();
+        let selection = picker::Selection {
+            path_names: HashSet::from(["Assets/a.txt".to_string()]),
+            guids: HashSet::from([PathBuf::from("a1")]),
+        };
+        let options = Arc::new(ExtractOptions::default());
+        let extraction = tokio::task::spawn_blocking(move || {
+            let reader = Cursor::new(package);
+            extract_package("test", reader, &output_dir, &options, Some(selection))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(extraction.warnings.is_empty());
+        for task in extraction.tasks {
+            assert!(task.await.unwrap().is_ok());
+        }
+        let assets = output.path().join("Assets");
+        assert_eq!(std::fs::read(assets.join("a.txt")).unwrap(), b"picked");
+        assert!(!assets.join("b.bin").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_queue_depth() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"a")
+            .add_asset("a2", "Assets/b.txt", b"")
+            .add_asset("a3", "Assets/c.txt", b"c")
+            .build();
+        let options = ExtractOptions {
+            write_slots: Some(Arc::new(Semaphore::new(1))),
+            ..Default::default()
+        };
+        let output = extract_with(package, options).await;
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            assert!(output.path().join("Assets").join(name).exists());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_batches() {
+        let mut builder = TestUnityPackageBuilder::new();
+        for i in 0..150 {
+            let path_name = format!("Assets/Small{}/{}.txt", i % 2, i);
+            builder = builder.add_asset(&format!("s{}", i), &path_name, b"small");
+        }
+        let large = vec![b'x'; batch::SMALL_FILE_SIZE + 1];
+        let package = builder.add_asset("l1", "Assets/large.bin", &large).build();
+
+        for group_by_folder in [false, true] {
+            let options = ExtractOptions {
+                group_by_folder,
+                ..Default::default()
+            };
+            let output = extract_with(package.clone(), options).await;
+            for folder in ["Assets/Small0", "Assets/Small1"] {
+                let small = std::fs::read_dir(output.path().join(folder)).unwrap();
+                assert_eq!(small.count(), 75);
+            }
+            let large_file = output.path().join("Assets/large.bin");
+            assert_eq!(std::fs::read(large_file).unwrap(), large);
+        }
+    }
+
+    struct PanickingTransform;
+
+    impl transform::Transform for PanickingTransform {
+        fn name(&self) -> &str {
+            "panic"
+        }
+
+        fn matches(&self, path: &Path) -> bool {
+            path.ends_with("bad.txt")
+        }
+
+        fn apply(&self, _path: &Path, _data: Vec<u8>) -> Vec<u8> {
+            panic!("pathological asset")
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_write_panic() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/bad.txt", b"bad")
+            .add_asset("a2", "Assets/good.txt", b"good")
+            .build();
+        let mut options = ExtractOptions::default();
+        options.transforms.push(Arc::new(PanickingTransform));
+        let output = tempfile::tempdir().unwrap();
+        let output_dir = output.path().to_path_buf();
+        let options = Arc::new(options);
+        let extraction = tokio::task::spawn_blocking(move || {
+            extract_package("test", Cursor::new(package), &output_dir, &options, None)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let mut results = Vec::new();
+        for task in extraction.tasks {
+            results.push(task.await.unwrap());
+        }
+        let failure = results.iter().find_map(|r| r.as_ref().err()).unwrap();
+        assert_eq!(failure.path, "Assets/bad.txt");
+        assert_eq!(failure.error.to_string(), "pathological asset");
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert!(output.path().join("Assets/good.txt").exists());
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let mut status = ExtractionStatus::default();
+        assert_eq!(status.exit_status(), ExitStatus::Success);
+        status.summary.add_failures(1);
+        assert_eq!(status.exit_status(), ExitStatus::PartialFailure);
+        status.summary.add_rejected(1);
+        assert_eq!(status.exit_status(), ExitStatus::PathRejected);
+        status.open_failed = true;
+        assert_eq!(status.exit_status(), ExitStatus::OpenFailed);
+        status.summary.add_unreadable_entries(1);
+        assert_eq!(status.exit_status(), ExitStatus::CorruptArchive);
+
+        let mut status = ExtractionStatus::default();
+        status
+            .summary
+            .add_warnings(vec![ExtractionWarning::TruncatedEntry {
+                entry: "a1/asset".to_string(),
+                path_name: None,
+                expected: 2,
+                actual: 1,
+            }]);
+        assert_eq!(status.exit_status(), ExitStatus::CorruptArchive);
+        status.timed_out = true;
+        assert_eq!(status.exit_status(), ExitStatus::TimedOut);
+    }
+
+    #[test]
+    fn test_raw() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/file.txt", b"data")
+            .add_entry_with_raw_name(b"../escape", b"nope")
+            .add_symlink("s1/asset", "/etc/passwd")
+            .build();
+        let output = tempfile::tempdir().unwrap();
+        let extraction = extract_raw(
+            "test",
+            Cursor::new(package),
+            output.path(),
+            &ExtractOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(extraction.raw_entries, 3);
+        assert_eq!(
+            std::fs::read(output.path().join("a1/asset")).unwrap(),
+            b"data"
+        );
+        assert_eq!(
+            std::fs::read(output.path().join("a1/pathname")).unwrap(),
+            b"Assets/file.txt"
+        );
+        assert_eq!(extraction.rejected_paths, 1);
+        assert!(!output.path().join("s1/asset").exists());
+        assert!(!output.path().join("Assets").exists());
+        // No pending file is left next to the entries.
+        let written = std::fs::read_dir(output.path().join("a1")).unwrap().count();
+        assert_eq!(written, 3);
+    }
 }
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+/// Importer settings worth auditing, looked up at any nesting level.
+const IMPORT_SETTING_KEYS: &[&str] = &[
+    "animationType",
+    "compressionFormat",
+    "enableMipMap",
+    "filterMode",
+    "forceToMono",
+    "globalScale",
+    "importAnimation",
+    "importMaterials",
+    "isReadable",
+    "loadType",
+    "maxTextureSize",
+    "meshCompression",
+    "quality",
+    "sRGBTexture",
+    "spriteMode",
+    "textureCompression",
+    "textureType",
+];
+
+/// How an asset is set up to be imported, as read from its `asset.meta`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSettings {
+    pub importer: Option<String>,
+    pub asset_bundle_name: Option<String>,
+    pub asset_bundle_variant: Option<String>,
+    pub labels: Vec<String>,
+    pub settings: BTreeMap<String, String>,
+}
+
+/// Looks up a top level `key: value` pair in `asset.meta` content.
+///
+/// This is a tolerant scan rather than a YAML parser: exporters disagree on
+/// line endings and spacing, but all put these keys at the start of a line.
+pub fn value<'a>(metadata: &'a str, key: &str) -> Option<&'a str> {
+    metadata.lines().find_map(|line| {
+        let (line_key, value) = line.split_once(':')?;
+        if line_key.trim_end() == key && !line.starts_with(char::is_whitespace) {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// Like `value`, but also finds keys nested under the importer section.
+fn nested_value<'a>(metadata: &'a str, key: &str) -> Option<&'a str> {
+    metadata.lines().find_map(|line| {
+        let (line_key, value) = line.trim_start().split_once(':')?;
+        (line_key.trim_end() == key).then(|| value.trim())
+    })
+}
+
+pub fn is_folder(metadata: &str) -> bool {
+    value(metadata, "folderAsset") == Some("yes")
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+pub fn import_settings(metadata: &str) -> ImportSettings {
+    let importer = metadata.lines().find_map(|line| {
+        let key = line.strip_suffix(':')?.trim_end_matches('\r');
+        (!line.starts_with(char::is_whitespace) && key.ends_with("Importer"))
+            .then(|| key.to_string())
+    });
+
+    let labels = metadata
+        .lines()
+        .skip_while(|line| line.trim_end() != "labels:")
+        .skip(1)
+        .map_while(|line| line.strip_prefix("- "))
+        .map(|label| label.trim().to_string())
+        .collect();
+
+    let settings = IMPORT_SETTING_KEYS
+        .iter()
+        .filter_map(|key| Some((key.to_string(), nested_value(metadata, key)?.to_string())))
+        .collect();
+
+    ImportSettings {
+        importer,
+        asset_bundle_name: non_empty(nested_value(metadata, "assetBundleName")),
+        asset_bundle_variant: non_empty(nested_value(metadata, "assetBundleVariant")),
+        labels,
+        settings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_folder() {
+        assert!(is_folder(
+            "fileFormatVersion: 2\nguid: 1\nfolderAsset: yes\n"
+        ));
+
+        // Windows line endings, odd spacing and a missing final newline.
+        assert!(is_folder("fileFormatVersion: 2\r\nfolderAsset: yes\r\n"));
+        assert!(is_folder("folderAsset :  yes"));
+
+        assert!(!is_folder("fileFormatVersion: 2\nfolderAsset: no\n"));
+        assert!(!is_folder("fileFormatVersion: 2\nguid: 1\n"));
+
+        // Nested keys belong to something else.
+        assert!(!is_folder("DefaultImporter:\n  folderAsset: yes\n"));
+    }
+
+    #[test]
+    fn test_import_settings() {
+        let metadata = "fileFormatVersion: 2\r
+guid: 0123\r
+labels:\r
+- Environment\r
+- Rock\r
+TextureImporter:\r
+  mipmaps:\r
+    enableMipMap: 1\r
+  maxTextureSize: 2048\r
+  platformSettings:\r
+  - serializedVersion: 3\r
+    maxTextureSize: 512\r
+  assetBundleName: rocks\r
+  assetBundleVariant: \r
+";
+        let settings = import_settings(metadata);
+        assert_eq!(settings.importer.as_deref(), Some("TextureImporter"));
+        assert_eq!(settings.labels, vec!["Environment", "Rock"]);
+        assert_eq!(settings.asset_bundle_name.as_deref(), Some("rocks"));
+        assert_eq!(settings.asset_bundle_variant, None);
+
+        // The importer wide value wins over platform overrides.
+        assert_eq!(settings.settings["maxTextureSize"], "2048");
+        assert_eq!(settings.settings["enableMipMap"], "1");
+        assert!(!settings.settings.contains_key("textureType"));
+
+        assert_eq!(
+            import_settings("fileFormatVersion: 2\n"),
+            ImportSettings::default()
+        );
+    }
+}
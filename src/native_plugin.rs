@@ -0,0 +1,208 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::transform::has_extension;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Architecture {
+    X86,
+    X64,
+    Arm,
+    Arm64,
+    Unknown(u32),
+}
+
+impl fmt::Display for Architecture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Architecture::X86 => f.write_str("x86"),
+            Architecture::X64 => f.write_str("x64"),
+            Architecture::Arm => f.write_str("arm"),
+            Architecture::Arm64 => f.write_str("arm64"),
+            Architecture::Unknown(machine) => write!(f, "unknown ({:#x})", machine),
+        }
+    }
+}
+
+/// A platform specific library found in a package.
+pub struct NativePlugin {
+    pub path: PathBuf,
+    pub format: &'static str,
+    pub architectures: Vec<Architecture>,
+}
+
+impl fmt::Display for NativePlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let architectures: Vec<String> = self.architectures.iter().map(|a| a.to_string()).collect();
+        write!(
+            f,
+            "{} ({} {})",
+            self.path.display(),
+            self.format,
+            architectures.join("+")
+        )
+    }
+}
+
+fn u16_le(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Returns `None` for managed (.NET) assemblies, which run anywhere.
+fn pe_architecture(data: &[u8]) -> Option<Architecture> {
+    let pe_offset = u32_le(data, 0x3c)? as usize;
+    if data.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let machine = u16_le(data, pe_offset + 4)?;
+    let optional_header = pe_offset + 24;
+    let data_directories = match u16_le(data, optional_header)? {
+        0x10b => optional_header + 96,
+        0x20b => optional_header + 112,
+        _ => return None,
+    };
+    // Data directory 14 is the CLR runtime header, only present in managed code.
+    let clr_header_size = u32_le(data, data_directories + 14 * 8 + 4).unwrap_or(0);
+    if clr_header_size != 0 {
+        return None;
+    }
+    Some(match machine {
+        0x14c => Architecture::X86,
+        0x8664 => Architecture::X64,
+        0x1c0 | 0x1c4 => Architecture::Arm,
+        0xaa64 => Architecture::Arm64,
+        other => Architecture::Unknown(other.into()),
+    })
+}
+
+fn elf_architecture(data: &[u8]) -> Option<Architecture> {
+    let machine = match data.get(5)? {
+        1 => u16_le(data, 18)?,
+        _ => u16::from_be_bytes(data.get(18..20)?.try_into().ok()?),
+    };
+    Some(match machine {
+        0x03 => Architecture::X86,
+        0x3e => Architecture::X64,
+        0x28 => Architecture::Arm,
+        0xb7 => Architecture::Arm64,
+        other => Architecture::Unknown(other.into()),
+    })
+}
+
+fn mach_o_architecture(cpu_type: u32) -> Architecture {
+    match cpu_type {
+        0x7 => Architecture::X86,
+        0x0100_0007 => Architecture::X64,
+        0xc => Architecture::Arm,
+        0x0100_000c => Architecture::Arm64,
+        other => Architecture::Unknown(other),
+    }
+}
+
+fn mach_o_architectures(data: &[u8]) -> Option<Vec<Architecture>> {
+    if data.starts_with(b"\xca\xfe\xba\xbe") {
+        let count = u32_be(data, 4)? as usize;
+        (0..count)
+            .map(|i| u32_be(data, 8 + i * 20).map(mach_o_architecture))
+            .collect()
+    } else {
+        Some(vec![mach_o_architecture(u32_le(data, 4)?)])
+    }
+}
+
+fn is_plugin_path(path: &Path) -> bool {
+    has_extension(path, &["dll", "so", "dylib"])
+        || path
+            .ancestors()
+            .skip(1)
+            .any(|ancestor| has_extension(ancestor, &["bundle"]))
+}
+
+/// Identifies native libraries among extracted assets, from their path and
+/// executable header.
+pub fn inspect(path: &Path, data: &[u8]) -> Option<NativePlugin> {
+    if !is_plugin_path(path) {
+        return None;
+    }
+
+    let (format, architectures) = if data.starts_with(b"MZ") {
+        ("PE", vec![pe_architecture(data)?])
+    } else if data.starts_with(b"\x7fELF") {
+        ("ELF", vec![elf_architecture(data)?])
+    } else if data.starts_with(b"\xcf\xfa\xed\xfe")
+        || data.starts_with(b"\xce\xfa\xed\xfe")
+        || data.starts_with(b"\xca\xfe\xba\xbe")
+    {
+        ("Mach-O", mach_o_architectures(data)?)
+    } else {
+        return None;
+    };
+
+    Some(NativePlugin {
+        path: path.to_path_buf(),
+        format,
+        architectures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pe(machine: u16, magic: u16, clr_size: u32) -> Vec<u8> {
+        let mut data = vec![0u8; 0x200];
+        data[..2].copy_from_slice(b"MZ");
+        data[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        data[0x84..0x86].copy_from_slice(&machine.to_le_bytes());
+        data[0x98..0x9a].copy_from_slice(&magic.to_le_bytes());
+        let clr = 0x98 + if magic == 0x20b { 112 } else { 96 } + 14 * 8 + 4;
+        data[clr..clr + 4].copy_from_slice(&clr_size.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_inspect() {
+        let dll = Path::new("Assets/Plugins/x86_64/native.dll");
+        let plugin = inspect(dll, &pe(0x8664, 0x20b, 0)).unwrap();
+        assert_eq!(plugin.format, "PE");
+        assert_eq!(plugin.architectures, vec![Architecture::X64]);
+
+        // Managed assemblies are not native plugins.
+        assert!(inspect(dll, &pe(0x14c, 0x10b, 0x48)).is_none());
+
+        // Only plugin paths are inspected.
+        assert!(inspect(Path::new("Assets/a.bytes"), &pe(0x8664, 0x20b, 0)).is_none());
+
+        let mut elf = b"\x7fELF\x02\x01".to_vec();
+        elf.resize(20, 0);
+        elf[18] = 0xb7;
+        let so = inspect(Path::new("Assets/Plugins/Android/libx.so"), &elf).unwrap();
+        assert_eq!(so.architectures, vec![Architecture::Arm64]);
+
+        let mut fat = b"\xca\xfe\xba\xbe\0\0\0\x02".to_vec();
+        fat.extend_from_slice(&0x0100_0007u32.to_be_bytes());
+        fat.extend_from_slice(&[0; 16]);
+        fat.extend_from_slice(&0x0100_000cu32.to_be_bytes());
+        let bundle = Path::new("Assets/Plugins/x.bundle/Contents/MacOS/x");
+        let universal = inspect(bundle, &fat).unwrap();
+        assert_eq!(
+            universal.to_string(),
+            format!("{} (Mach-O x64+arm64)", bundle.display())
+        );
+    }
+}
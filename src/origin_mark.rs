@@ -0,0 +1,40 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Whether this platform has a way to flag files as coming from an
+/// untrusted source.
+pub const SUPPORTED: bool = cfg!(any(target_os = "macos", windows));
+
+/// `--mark-untrusted`: flags a file the way browsers flag downloads, so
+/// Gatekeeper or SmartScreen and Office's protected view treat it as such.
+#[cfg(target_os = "macos")]
+pub fn mark_untrusted(file: &File, _path: &Path) -> Result<(), io::Error> {
+    use xattr::FileExt;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    // flags;timestamp;agent;event id, 0081 being "downloaded, not approved".
+    let value = format!("0081;{:08x};rust-unityextractor;", now);
+    file.set_xattr("com.apple.quarantine", value.as_bytes())
+}
+
+/// `--mark-untrusted`: flags a file the way browsers flag downloads, so
+/// Gatekeeper or SmartScreen and Office's protected view treat it as such.
+#[cfg(windows)]
+pub fn mark_untrusted(_file: &File, path: &Path) -> Result<(), io::Error> {
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":Zone.Identifier");
+    // Zone 3 is the Internet zone.
+    std::fs::write(stream, "[ZoneTransfer]\r\nZoneId=3\r\n")
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn mark_untrusted(_file: &File, _path: &Path) -> Result<(), io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "files can't be marked as untrusted on this platform",
+    ))
+}
@@ -0,0 +1,341 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use cap_std::ambient_authority;
+use cap_std::fs::{Dir, OpenOptions};
+use log::warn;
+
+use crate::sharing;
+
+/// `--sync`: what is flushed to disk before a file counts as extracted.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SyncPolicy {
+    /// Leave it to the OS, the fastest.
+    #[default]
+    None,
+    /// fsync each file.
+    File,
+    /// fsync the folder of each file, so its entry survives a crash.
+    Dir,
+    All,
+}
+
+impl SyncPolicy {
+    pub fn syncs_files(self) -> bool {
+        matches!(self, SyncPolicy::File | SyncPolicy::All)
+    }
+
+    pub fn syncs_folders(self) -> bool {
+        matches!(self, SyncPolicy::Dir | SyncPolicy::All)
+    }
+}
+
+impl FromStr for SyncPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(SyncPolicy::None),
+            "file" => Ok(SyncPolicy::File),
+            "dir" => Ok(SyncPolicy::Dir),
+            "all" => Ok(SyncPolicy::All),
+            _ => Err(format!("unknown sync policy {:?}", s)),
+        }
+    }
+}
+
+/// `--also-to`: a second output directory getting a copy of every file and
+/// folder, laid out as below the first.
+pub struct Mirror {
+    pub output_dir: PathBuf,
+    pub mirror_dir: PathBuf,
+}
+
+impl Mirror {
+    /// The mirror of a package's output directory.
+    pub fn dir_for(&self, output_dir: &Path) -> PathBuf {
+        match output_dir.strip_prefix(&self.output_dir) {
+            Ok(relative) => self.mirror_dir.join(relative),
+            Err(_) => self.mirror_dir.clone(),
+        }
+    }
+}
+
+/// A file written under a temporary name next to its destination, so a
+/// crash never leaves a truncated file under the final name.
+#[derive(Clone)]
+pub struct PendingFile {
+    temp: PathBuf,
+    relative: PathBuf,
+}
+
+/// The pending files of every output root, for a run cut short by
+/// `--timeout` to remove those whose writes never finished.
+static PENDING: Mutex<Vec<(Arc<Dir>, PathBuf)>> = Mutex::new(Vec::new());
+
+fn pending_files() -> MutexGuard<'static, Vec<(Arc<Dir>, PathBuf)>> {
+    PENDING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Removes the pending files still being written, returning how many.
+pub fn discard_all_pending() -> usize {
+    let pending = std::mem::take(&mut *pending_files());
+    let mut removed = 0;
+    for (dir, temp) in pending {
+        match dir.remove_file(&temp) {
+            Ok(()) => removed += 1,
+            Err(e) => warn!("cannot remove {}: {}", temp.display(), e),
+        }
+    }
+    removed
+}
+
+/// The output directory, opened once. Files and folders are created
+/// relative to it through cap-std, which resolves paths beneath the handle
+/// (openat2 with RESOLVE_BENEATH on Linux) and refuses any path escaping
+/// it, whether through `..`, an absolute path or a symlink planted on disk.
+pub struct OutputRoot {
+    dir: Arc<Dir>,
+    path: PathBuf,
+    /// Folders known to exist, so that files sharing a folder don't each
+    /// walk it again.
+    created: Mutex<HashSet<PathBuf>>,
+    temp_files: AtomicU64,
+    /// `--also-to`: where the same files are written next.
+    mirror: Option<Box<OutputRoot>>,
+}
+
+impl OutputRoot {
+    pub fn open(path: &Path) -> Result<OutputRoot, io::Error> {
+        std::fs::create_dir_all(path)?;
+        Ok(OutputRoot {
+            dir: Arc::new(Dir::open_ambient_dir(path, ambient_authority())?),
+            path: path.to_path_buf(),
+            created: Mutex::new(HashSet::new()),
+            temp_files: AtomicU64::new(0),
+            mirror: None,
+        })
+    }
+
+    /// Also opens `mirror`, for the writers to copy their files to.
+    pub fn with_mirror(mut self, mirror: &Path) -> Result<OutputRoot, io::Error> {
+        self.mirror = Some(Box::new(OutputRoot::open(mirror)?));
+        Ok(self)
+    }
+
+    pub fn mirror(&self) -> Option<&OutputRoot> {
+        self.mirror.as_deref()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Another handle on the same folder, without the mirror, to remove
+    /// files once this one is gone.
+    pub fn try_clone(&self) -> Result<OutputRoot, io::Error> {
+        Ok(OutputRoot {
+            dir: Arc::new(self.dir.try_clone()?),
+            path: self.path.clone(),
+            created: Mutex::new(HashSet::new()),
+            temp_files: AtomicU64::new(0),
+            mirror: None,
+        })
+    }
+
+    /// Whether anything is at `relative`, a dangling symlink included.
+    pub fn exists(&self, relative: &Path) -> bool {
+        self.dir.symlink_metadata(relative).is_ok()
+    }
+
+    pub fn remove_file(&self, relative: &Path) -> Result<(), io::Error> {
+        sharing::retry(|| self.dir.remove_file(relative))
+    }
+
+    /// Removes `relative` when it is an empty folder.
+    pub fn remove_dir(&self, relative: &Path) -> Result<(), io::Error> {
+        self.dir.remove_dir(relative)?;
+        self.created().remove(relative);
+        Ok(())
+    }
+
+    pub fn create_dir_all(&self, relative: &Path) -> Result<(), io::Error> {
+        if relative.as_os_str().is_empty() || self.created().contains(relative) {
+            return Ok(());
+        }
+        self.dir.create_dir_all(relative)?;
+        let mut created = self.created();
+        for folder in relative.ancestors() {
+            if folder.as_os_str().is_empty() || !created.insert(folder.to_path_buf()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn created(&self) -> std::sync::MutexGuard<'_, HashSet<PathBuf>> {
+        self.created.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Flushes the folder holding `relative`, making its entry durable.
+    pub fn sync_parent(&self, relative: &Path) -> Result<(), io::Error> {
+        // Windows can't open folders as files, nor needs it.
+        if cfg!(unix) {
+            let parent = match relative.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            self.dir.open(parent)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Creates, or truncates, a file along with its missing parents.
+    pub fn create_file(&self, relative: &Path) -> Result<std::fs::File, io::Error> {
+        if let Some(parent) = relative.parent() {
+            self.create_dir_all(parent)?;
+        }
+        match self.create(relative) {
+            // A folder was removed behind our back, create it again.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.created().clear();
+                if let Some(parent) = relative.parent() {
+                    self.create_dir_all(parent)?;
+                }
+                Ok(self.create(relative)?.into_std())
+            }
+            file => Ok(file?.into_std()),
+        }
+    }
+
+    fn create(&self, relative: &Path) -> Result<cap_std::fs::File, io::Error> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Others may read, write or delete the file while it's open, so a
+        // scanner opening it mid-write doesn't meet a sharing violation.
+        #[cfg(windows)]
+        {
+            use cap_std::fs::OpenOptionsExt;
+            const FILE_SHARE_READ_WRITE_DELETE: u32 = 0x1 | 0x2 | 0x4;
+            options.share_mode(FILE_SHARE_READ_WRITE_DELETE);
+        }
+        sharing::retry(|| self.dir.open_with(relative, &options))
+    }
+
+    /// Creates `.<pid>-<n>.part` next to `relative`, to be renamed over it
+    /// once written. The name is kept short so any name that fits the
+    /// filesystem's limit can be written this way.
+    pub fn create_pending(
+        &self,
+        relative: &Path,
+    ) -> Result<(std::fs::File, PendingFile), io::Error> {
+        let number = self.temp_files.fetch_add(1, Ordering::Relaxed);
+        let temp_name = format!(".{}-{}.part", std::process::id(), number);
+        let pending = PendingFile {
+            temp: relative.with_file_name(temp_name),
+            relative: relative.to_path_buf(),
+        };
+        let file = self.create_file(&pending.temp)?;
+        pending_files().push((self.dir.clone(), pending.temp.clone()));
+        Ok((file, pending))
+    }
+
+    fn forget(&self, pending: &PendingFile) {
+        pending_files().retain(|(dir, temp)| !Arc::ptr_eq(dir, &self.dir) || *temp != pending.temp);
+    }
+
+    /// Moves a completely written file to its final name, replacing what
+    /// was there.
+    pub fn persist(&self, pending: &PendingFile) -> Result<(), io::Error> {
+        sharing::retry(|| self.dir.rename(&pending.temp, &self.dir, &pending.relative))?;
+        self.forget(pending);
+        Ok(())
+    }
+
+    /// Where a pending file is, for the APIs that only take paths.
+    pub fn pending_path(&self, pending: &PendingFile) -> PathBuf {
+        self.path.join(&pending.temp)
+    }
+
+    pub fn discard(&self, pending: &PendingFile) {
+        self.forget(pending);
+        if let Err(e) = sharing::retry(|| self.dir.remove_file(&pending.temp)) {
+            warn!("cannot remove {}: {}", pending.temp.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_root() {
+        let output = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let root = OutputRoot::open(&output.path().join("out")).unwrap();
+
+        root.create_file(Path::new("Assets/a/b.txt")).unwrap();
+        assert!(output.path().join("out/Assets/a/b.txt").exists());
+        root.sync_parent(Path::new("Assets/a/b.txt")).unwrap();
+        root.sync_parent(Path::new("top.txt")).unwrap();
+
+        let (file, pending) = root.create_pending(Path::new("Assets/a/b.txt")).unwrap();
+        std::io::Write::write_all(&mut &file, b"new").unwrap();
+        drop(file);
+        let original = std::fs::read(output.path().join("out/Assets/a/b.txt")).unwrap();
+        assert!(original.is_empty());
+        root.persist(&pending).unwrap();
+        let replaced = std::fs::read(output.path().join("out/Assets/a/b.txt")).unwrap();
+        assert_eq!(replaced, b"new");
+        assert_eq!(
+            std::fs::read_dir(output.path().join("out/Assets/a"))
+                .unwrap()
+                .count(),
+            1
+        );
+        let (_, pending) = root.create_pending(Path::new("Assets/a/b.txt")).unwrap();
+        root.discard(&pending);
+        assert_eq!(
+            std::fs::read_dir(output.path().join("out/Assets/a"))
+                .unwrap()
+                .count(),
+            1
+        );
+        // Names close to the 255 bytes most filesystems allow still fit.
+        let long = Path::new("Assets/a").join(format!("{}.txt", "l".repeat(250)));
+        let (_, pending) = root.create_pending(&long).unwrap();
+        root.persist(&pending).unwrap();
+        assert!(output.path().join("out").join(&long).is_file());
+
+        let mirror = Mirror {
+            output_dir: PathBuf::from("out"),
+            mirror_dir: PathBuf::from("nas"),
+        };
+        assert_eq!(
+            mirror.dir_for(Path::new("out/Rocks")),
+            Path::new("nas/Rocks")
+        );
+        assert_eq!(mirror.dir_for(Path::new("elsewhere")), Path::new("nas"));
+
+        // Cached folders removed meanwhile are created again.
+        std::fs::remove_dir_all(output.path().join("out/Assets")).unwrap();
+        root.create_file(Path::new("Assets/a/c.txt")).unwrap();
+        assert!(output.path().join("out/Assets/a/c.txt").exists());
+
+        assert!(root.create_file(Path::new("../escape.txt")).is_err());
+        assert!(root.create_file(&outside.path().join("abs.txt")).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.path(), output.path().join("out/link")).unwrap();
+            assert!(root.create_file(Path::new("link/escape.txt")).is_err());
+            assert!(root.create_dir_all(Path::new("link/sub")).is_err());
+            assert!(!outside.path().join("escape.txt").exists());
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::io;
+use std::path::Path;
+
+use log::{error, warn};
+
+use crate::picker::index_guids;
+use crate::volumes::Volumes;
+use crate::ExtractOptions;
+
+/// What to do with files of the output directory a package also contains.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OverwritePolicy {
+    /// Don't extract a package over an existing project.
+    #[default]
+    Refuse,
+    /// `--merge`: keep the files already there, only add the new ones.
+    Merge,
+    /// `--force`: replace them.
+    Force,
+}
+
+/// The files of `output_dir` that extracting the package would replace.
+/// Only looked for when `output_dir` already has an `Assets` folder with
+/// something in it, as finding them means reading the whole package.
+pub fn conflicts(
+    input_path: &str,
+    output_dir: &Path,
+    options: &ExtractOptions,
+) -> Result<Vec<String>, io::Error> {
+    let has_assets = std::fs::read_dir(output_dir.join("Assets"))
+        .is_ok_and(|mut entries| entries.next().is_some());
+    if !has_assets {
+        return Ok(Vec::new());
+    }
+    let index = index_guids(Volumes::open(input_path)?)?;
+    Ok(index
+        .into_iter()
+        .filter(|(_, _, size)| size.is_some())
+        .filter_map(|(guid, path_name, _)| written_path(options, &guid, path_name).ok())
+        .filter(|target_path| output_dir.join(target_path).is_file())
+        .collect())
+}
+
+/// Where the writer puts `path_name`: moved by `--map-path`, then
+/// rewritten by `--case` and `--ascii`.
+fn written_path(
+    options: &ExtractOptions,
+    guid: &Path,
+    path_name: String,
+) -> Result<String, io::Error> {
+    let resolved = options
+        .resolver
+        .as_ref()
+        .and_then(|resolver| resolver.resolve(&guid.to_string_lossy(), &path_name))
+        .unwrap_or(path_name);
+    options.target_path(&options.rewrite_path_name(&resolved))
+}
+
+/// Whether the default policy keeps the package from being extracted, as it
+/// would replace files of `output_dir` or of its `--also-to` mirror. Logs
+/// why.
+pub fn refuses(input_path: &str, output_dir: &Path, options: &ExtractOptions) -> bool {
+    if options.overwrite != OverwritePolicy::Refuse || options.journal.is_resuming() {
+        return false;
+    }
+    let mut dirs = vec![output_dir.to_path_buf()];
+    dirs.extend(options.mirror.as_ref().map(|m| m.dir_for(output_dir)));
+    for dir in dirs {
+        match conflicts(input_path, &dir, options) {
+            Ok(conflicts) if conflicts.is_empty() => {}
+            Ok(conflicts) => {
+                error!(
+                    "extracting {} would replace {} files in {}, such as {}; use --merge to keep them or --force to replace them",
+                    input_path,
+                    conflicts.len(),
+                    dir.display(),
+                    conflicts[0]
+                );
+                return true;
+            }
+            Err(e) => warn!("cannot look for files {} would replace: {}", input_path, e),
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_rewrite::PathCase;
+    use crate::resolver::PrefixMap;
+    use crate::testing::TestUnityPackageBuilder;
+
+    #[test]
+    fn test_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let input_path = dir.path().join("test.unitypackage");
+        let package = TestUnityPackageBuilder::new()
+            .add_folder("f1", "Assets/Rocks")
+            .add_asset("a1", "Assets/Rocks/a.txt", b"a")
+            .add_asset("a2", "Assets\\Rocks\\b.txt", b"b")
+            .build();
+        std::fs::write(&input_path, package).unwrap();
+        let input_path = input_path.to_str().unwrap();
+        let output = dir.path().join("project");
+        let options = ExtractOptions::default();
+
+        assert!(conflicts(input_path, &output, &options).unwrap().is_empty());
+        std::fs::create_dir_all(output.join("Assets/Rocks")).unwrap();
+        std::fs::write(output.join("Assets/Other.txt"), b"other").unwrap();
+        assert!(conflicts(input_path, &output, &options).unwrap().is_empty());
+        std::fs::write(output.join("Assets/Rocks/b.txt"), b"old").unwrap();
+        let found = conflicts(input_path, &output, &options).unwrap();
+        assert_eq!(found, vec!["Assets/Rocks/b.txt"]);
+
+        // Looked for where --map-path and --case put them.
+        let options = ExtractOptions {
+            resolver: Some(Box::new(
+                PrefixMap::parse(&["Assets/Rocks=Assets/Stones".to_string()]).unwrap(),
+            )),
+            case: PathCase::Lower,
+            ..Default::default()
+        };
+        assert!(conflicts(input_path, &output, &options).unwrap().is_empty());
+        std::fs::create_dir_all(output.join("Assets/stones")).unwrap();
+        std::fs::write(output.join("Assets/stones/a.txt"), b"old").unwrap();
+        let found = conflicts(input_path, &output, &options).unwrap();
+        assert_eq!(found, vec!["Assets/stones/a.txt"]);
+    }
+}
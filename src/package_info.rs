@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, warn};
+use serde_json::{json, Value};
+
+use crate::zip_input;
+
+/// What the Asset Store says about a package: the JSON Unity keeps next to
+/// the packages it downloads, or in their gzip header.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PackageInfo {
+    pub title: String,
+    pub publisher: Option<String>,
+    pub id: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Ids are numbers in some versions of the Asset Store and strings in
+/// others.
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+impl PackageInfo {
+    pub fn from_json(value: &Value) -> Option<PackageInfo> {
+        Some(PackageInfo {
+            title: text(&value["title"])?,
+            publisher: text(&value["publisher"]["label"]).or_else(|| text(&value["publisher"])),
+            id: text(&value["id"]).or_else(|| text(&value["link"]["id"])),
+            version: text(&value["version"]),
+        })
+    }
+
+    /// The title made into a single folder name.
+    pub fn folder_name(&self) -> String {
+        folder_name(&self.title)
+    }
+
+    pub fn to_json(&self, input_path: &str) -> Value {
+        json!({
+            "path": input_path,
+            "title": self.title,
+            "publisher": self.publisher,
+            "id": self.id,
+            "version": self.version,
+        })
+    }
+}
+
+/// Turns a package title or file name into a name usable as a single folder
+/// on any platform.
+pub fn folder_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if name.is_empty() {
+        "package".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// The package's file name without its extension nor volume number. The
+/// zip's name for the only package of a zip, the package's for `zip!name`.
+pub fn file_name(input_path: &str) -> String {
+    let input_path = match zip_input::split(input_path) {
+        Some((_, Some(inner))) => inner,
+        _ => input_path,
+    };
+    let name = Path::new(input_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let name = name.strip_suffix(".001").unwrap_or(&name);
+    let name = name.strip_suffix(".unitypackage").unwrap_or(name);
+    let name = name.strip_suffix(".zip").unwrap_or(name);
+    folder_name(name)
+}
+
+fn sidecar_paths(input_path: &str) -> [PathBuf; 2] {
+    [
+        PathBuf::from(format!("{}.json", input_path)),
+        Path::new(input_path).with_extension("json"),
+    ]
+}
+
+/// Reads `<package>.unitypackage.json` or `<package>.json`, when there is
+/// one next to the package.
+pub fn read_sidecar(input_path: &str) -> Option<PackageInfo> {
+    let path = sidecar_paths(input_path)
+        .into_iter()
+        .find(|p| p.is_file())?;
+    let value = std::fs::read(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_slice::<Value>(&data).map_err(|e| e.to_string()));
+    match value.map(|value| PackageInfo::from_json(&value)) {
+        Ok(Some(info)) => {
+            debug!("{} describes {}", path.display(), info.title);
+            Some(info)
+        }
+        Ok(None) => {
+            warn!("{} has no package title, ignoring it", path.display());
+            None
+        }
+        Err(e) => {
+            warn!("cannot read {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let input_path = dir.path().join("Rocks.unitypackage");
+        let input_path = input_path.to_str().unwrap();
+        assert_eq!(read_sidecar(input_path), None);
+
+        let sidecar = json!({
+            "title": "Rocks: Vol. 2/3",
+            "id": 12345,
+            "version": "1.2.0",
+            "publisher": {"label": "Stone Co", "id": "42"},
+            "link": {"id": "12345", "type": "content"},
+        });
+        std::fs::write(dir.path().join("Rocks.json"), sidecar.to_string()).unwrap();
+        let info = read_sidecar(input_path).unwrap();
+        assert_eq!(info.publisher.as_deref(), Some("Stone Co"));
+        assert_eq!(info.id.as_deref(), Some("12345"));
+        assert_eq!(info.folder_name(), "Rocks_ Vol. 2_3");
+        assert_eq!(info.to_json(input_path)["version"], "1.2.0");
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("dl/Rocks.unitypackage"), "Rocks");
+        assert_eq!(file_name("dl/Rocks v2.unitypackage.001"), "Rocks v2");
+        assert_eq!(file_name("dl/..unitypackage"), "package");
+        assert_eq!(file_name("dl/Rocks.zip"), "Rocks");
+        assert_eq!(file_name("dl/Pack.zip!Rocks/Rocks.unitypackage"), "Rocks");
+    }
+}
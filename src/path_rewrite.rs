@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// `--case`: what becomes of the case of pathnames.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PathCase {
+    #[default]
+    Preserve,
+    /// Every component lowercased but the first, which Unity wants as
+    /// `Assets` or `Packages`.
+    Lower,
+}
+
+impl FromStr for PathCase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preserve" => Ok(PathCase::Preserve),
+            "lower" => Ok(PathCase::Lower),
+            _ => Err(format!("unknown path case {:?}", s)),
+        }
+    }
+}
+
+impl PathCase {
+    pub fn apply(self, path_name: &str) -> String {
+        match self {
+            PathCase::Preserve => path_name.to_string(),
+            PathCase::Lower => match path_name.find(['/', '\\']) {
+                Some(end) => {
+                    let (root, rest) = path_name.split_at(end);
+                    format!("{}{}", root, rest.to_lowercase())
+                }
+                None => path_name.to_string(),
+            },
+        }
+    }
+}
+
+/// `--ascii`: what becomes of the characters of pathnames outside ASCII,
+/// for toolchains and file systems that choke on them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AsciiPaths {
+    #[default]
+    Keep,
+    /// The closest ASCII, `é` becoming `e` and `日本` `Ri Ben`.
+    Transliterate,
+    /// `%XX` for each byte of their UTF-8, and `%` itself, which can be
+    /// decoded back.
+    Percent,
+}
+
+impl FromStr for AsciiPaths {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(AsciiPaths::Keep),
+            "transliterate" => Ok(AsciiPaths::Transliterate),
+            "percent" => Ok(AsciiPaths::Percent),
+            _ => Err(format!("unknown ASCII conversion {:?}", s)),
+        }
+    }
+}
+
+impl AsciiPaths {
+    pub fn apply(self, path_name: &str) -> String {
+        match self {
+            AsciiPaths::Keep => path_name.to_string(),
+            AsciiPaths::Transliterate => path_name
+                .split_inclusive(['/', '\\'])
+                .map(|component| {
+                    let name = component.trim_end_matches(['/', '\\']);
+                    let separator = &component[name.len()..];
+                    if name.is_ascii() {
+                        return component.to_string();
+                    }
+                    // Whole names, as spacing depends on the neighbours.
+                    // Some would make more components, ½ becoming 1/2.
+                    let name = deunicode::deunicode(name).replace(['/', '\\'], "_");
+                    name + separator
+                })
+                .collect(),
+            AsciiPaths::Percent => {
+                let mut converted = String::with_capacity(path_name.len());
+                for c in path_name.chars() {
+                    if c.is_ascii() && c != '%' {
+                        converted.push(c);
+                        continue;
+                    }
+                    let mut bytes = [0; 4];
+                    for byte in c.encode_utf8(&mut bytes).bytes() {
+                        converted.push_str(&format!("%{:02X}", byte));
+                    }
+                }
+                converted
+            }
+        }
+    }
+}
+
+/// The pathnames of files once `--case` and `--ascii` rewrote them, so
+/// two files rewritten to the same one don't overwrite each other. Folders
+/// rewritten to the same one end up as one, their files checked one by one.
+#[derive(Default)]
+pub struct Renames {
+    /// Original pathname of each one given out.
+    claimed: HashMap<String, String>,
+}
+
+impl Renames {
+    /// The pathname a file is written to, `rewritten` with `~2`, `~3`...
+    /// before its extension when another file already took it.
+    pub fn claim(&mut self, path_name: &str, rewritten: String) -> String {
+        let mut candidate = rewritten.clone();
+        let mut n = 1;
+        while let Some(original) = self.claimed.get(&candidate) {
+            if original == path_name {
+                return candidate;
+            }
+            n += 1;
+            candidate = numbered(&rewritten, n);
+        }
+        self.claimed
+            .insert(candidate.clone(), path_name.to_string());
+        candidate
+    }
+}
+
+fn numbered(path_name: &str, n: u32) -> String {
+    let name_start = path_name.rfind(['/', '\\']).map_or(0, |i| i + 1);
+    let end = match path_name[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => name_start + dot,
+        _ => path_name.len(),
+    };
+    format!("{}~{}{}", &path_name[..end], n, &path_name[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(renames: &mut Renames, path_name: &str) -> String {
+        renames.claim(path_name, PathCase::Lower.apply(path_name))
+    }
+
+    #[test]
+    fn test_path_case() {
+        assert_eq!(
+            PathCase::Lower.apply("Assets/Rocks/Granite.PNG"),
+            "Assets/rocks/granite.png"
+        );
+        assert_eq!(PathCase::Lower.apply("Assets"), "Assets");
+        assert_eq!(
+            PathCase::Preserve.apply("Assets/Rocks/Granite.PNG"),
+            "Assets/Rocks/Granite.PNG"
+        );
+
+        let mut renames = Renames::default();
+        assert_eq!(claim(&mut renames, "Assets/Rock.png"), "Assets/rock.png");
+        assert_eq!(claim(&mut renames, "Assets/ROCK.png"), "Assets/rock~2.png");
+        assert_eq!(claim(&mut renames, "Assets/rock.png"), "Assets/rock~3.png");
+        assert_eq!(claim(&mut renames, "Assets/ROCK.png"), "Assets/rock~2.png");
+        assert_eq!(claim(&mut renames, "Assets/A/.Hidden"), "Assets/a/.hidden");
+        assert_eq!(
+            claim(&mut renames, "Assets/a/.hidden"),
+            "Assets/a/.hidden~2"
+        );
+        assert_eq!(
+            claim(&mut renames, "Assets/Dir.d/Make"),
+            "Assets/dir.d/make"
+        );
+        assert_eq!(
+            claim(&mut renames, "Assets/dir.d/MAKE"),
+            "Assets/dir.d/make~2"
+        );
+    }
+
+    #[test]
+    fn test_ascii_paths() {
+        let path_name = "Assets/Textures/Café ½ 100%.png";
+        assert_eq!(AsciiPaths::Keep.apply(path_name), path_name);
+        assert_eq!(
+            AsciiPaths::Transliterate.apply(path_name),
+            "Assets/Textures/Cafe 1_2 100%.png"
+        );
+        assert_eq!(
+            AsciiPaths::Percent.apply(path_name),
+            "Assets/Textures/Caf%C3%A9 %C2%BD 100%25.png"
+        );
+        assert_eq!(
+            AsciiPaths::Transliterate.apply("Assets/日本/a.txt"),
+            "Assets/Ri Ben/a.txt"
+        );
+    }
+}
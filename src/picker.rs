@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use flate2::read::GzDecoder;
+use log::warn;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::DefaultTerminal;
+
+use crate::guid_of;
+use crate::sanitize_path::SanitizePolicy;
+use crate::volumes::Volumes;
+
+/// Reads every pathname of a package with the size of its asset, `None` for
+/// folders, without keeping any asset data in memory.
+pub fn index_package<R: Read>(reader: R) -> Result<Vec<(String, Option<u64>)>, io::Error> {
+    Ok(index_guids(reader)?
+        .into_iter()
+        .map(|(_, path_name, size)| (path_name, size))
+        .collect())
+}
+
+/// `index_package` with the GUID of each pathname.
+pub fn index_guids<R: Read>(reader: R) -> Result<Vec<(PathBuf, String, Option<u64>)>, io::Error> {
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let mut path_names: Vec<(PathBuf, String)> = Vec::new();
+
+    for entry_result in archive.entries()? {
+        let mut entry = match entry_result {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("error reading entry from archive: {}", e);
+                continue;
+            }
+        };
+        let path = entry.path()?.to_path_buf();
+        let guid = guid_of(&path).to_path_buf();
+        if path.ends_with("asset") {
+            sizes.insert(guid, entry.header().size()?);
+        } else if path.ends_with("pathname") {
+            let mut path_name = String::new();
+            entry.read_to_string(&mut path_name)?;
+            path_names.push((guid, path_name));
+        }
+    }
+
+    Ok(path_names
+        .into_iter()
+        .map(|(guid, path_name)| {
+            let size = sizes.get(&guid).copied();
+            (guid, path_name, size)
+        })
+        .collect())
+}
+
+/// What was picked in `choose`.
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub path_names: HashSet<String>,
+    /// Their GUIDs, so the data of the others can be skipped unread.
+    pub guids: HashSet<PathBuf>,
+}
+
+struct Node {
+    name: String,
+    depth: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    size: u64,
+    leaves: u64,
+    selected_leaves: u64,
+    expanded: bool,
+    /// Pathnames, as written in the archive, that resolve to this node.
+    path_names: Vec<String>,
+}
+
+/// The package's pathnames as a folder tree with checkboxes.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new(index: Vec<(String, u64)>, policy: SanitizePolicy) -> Tree {
+        let mut nodes = vec![Node {
+            name: String::new(),
+            depth: 0,
+            parent: None,
+            children: Vec::new(),
+            size: 0,
+            leaves: 0,
+            selected_leaves: 0,
+            expanded: true,
+            path_names: Vec::new(),
+        }];
+        let mut lookup: BTreeMap<(usize, String), usize> = BTreeMap::new();
+
+        for (path_name, size) in index {
+            // Rejected entries won't be extracted anyway.
+            let Ok(sanitized) = policy.sanitize(&path_name) else {
+                continue;
+            };
+            let mut current = 0;
+            for component in sanitized.split('/').filter(|c| !c.is_empty()) {
+                nodes[current].size += size;
+                let key = (current, component.to_string());
+                current = *lookup.entry(key).or_insert_with(|| {
+                    nodes.push(Node {
+                        name: component.to_string(),
+                        depth: nodes[current].depth + 1,
+                        parent: Some(current),
+                        children: Vec::new(),
+                        size: 0,
+                        leaves: 0,
+                        selected_leaves: 0,
+                        expanded: false,
+                        path_names: Vec::new(),
+                    });
+                    let child = nodes.len() - 1;
+                    nodes[current].children.push(child);
+                    child
+                });
+            }
+            nodes[current].size += size;
+            nodes[current].path_names.push(path_name);
+        }
+
+        let mut tree = Tree { nodes };
+        for node in 0..tree.nodes.len() {
+            let mut children = std::mem::take(&mut tree.nodes[node].children);
+            children.sort_by(|a, b| tree.nodes[*a].name.cmp(&tree.nodes[*b].name));
+            tree.nodes[node].children = children;
+        }
+        tree.count_leaves(0);
+        tree
+    }
+
+    fn count_leaves(&mut self, node: usize) -> u64 {
+        let children = self.nodes[node].children.clone();
+        let leaves = if children.is_empty() {
+            1
+        } else {
+            children.into_iter().map(|c| self.count_leaves(c)).sum()
+        };
+        self.nodes[node].leaves = leaves;
+        self.nodes[node].selected_leaves = leaves;
+        leaves
+    }
+
+    /// Nodes shown on screen, in display order.
+    fn visible(&self) -> Vec<usize> {
+        let mut rows = Vec::new();
+        let mut stack: Vec<usize> = self.nodes[0].children.iter().rev().copied().collect();
+        while let Some(node) = stack.pop() {
+            rows.push(node);
+            if self.nodes[node].expanded {
+                stack.extend(self.nodes[node].children.iter().rev());
+            }
+        }
+        rows
+    }
+
+    fn set_selected(&mut self, node: usize, selected: bool) {
+        let children = self.nodes[node].children.clone();
+        for child in children {
+            self.set_selected(child, selected);
+        }
+        let leaves = self.nodes[node].leaves;
+        self.nodes[node].selected_leaves = if selected { leaves } else { 0 };
+    }
+
+    /// Selects the whole subtree, or clears it if it was fully selected.
+    fn toggle(&mut self, node: usize) {
+        let before = self.nodes[node].selected_leaves;
+        self.set_selected(node, before < self.nodes[node].leaves);
+        let after = self.nodes[node].selected_leaves;
+
+        let mut ancestor = self.nodes[node].parent;
+        while let Some(parent) = ancestor {
+            let parent_node = &mut self.nodes[parent];
+            parent_node.selected_leaves = parent_node.selected_leaves + after - before;
+            ancestor = parent_node.parent;
+        }
+    }
+
+    fn selected_size(&self) -> u64 {
+        self.nodes
+            .iter()
+            .filter(|node| node.children.is_empty() && node.selected_leaves > 0)
+            .map(|node| node.size)
+            .sum()
+    }
+
+    /// Pathnames to extract; folders are kept when anything in them is.
+    fn selection(&self) -> HashSet<String> {
+        self.nodes
+            .iter()
+            .filter(|node| node.selected_leaves > 0)
+            .flat_map(|node| node.path_names.iter().cloned())
+            .collect()
+    }
+
+    fn row(&self, node: usize) -> String {
+        let node = &self.nodes[node];
+        let fold = match (node.children.is_empty(), node.expanded) {
+            (true, _) => ' ',
+            (false, true) => '▾',
+            (false, false) => '▸',
+        };
+        let check = if node.selected_leaves == node.leaves {
+            "[x]"
+        } else if node.selected_leaves > 0 {
+            "[-]"
+        } else {
+            "[ ]"
+        };
+        format!(
+            "{}{} {} {}  {}",
+            "  ".repeat(node.depth - 1),
+            fold,
+            check,
+            node.name,
+            human_size(node.size)
+        )
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Returns whether the user confirmed the selection.
+fn run(terminal: &mut DefaultTerminal, title: &str, tree: &mut Tree) -> Result<bool, io::Error> {
+    let mut state = ListState::default().with_selected(Some(0));
+    let help = "↑/↓ move  space toggle  ←/→ fold  a all  enter extract  q skip";
+
+    loop {
+        let rows = tree.visible();
+        let status = format!(
+            "{} files selected, {}",
+            tree.nodes[0].selected_leaves,
+            human_size(tree.selected_size())
+        );
+        terminal.draw(|frame| {
+            let [list_area, status_area, help_area] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+            let items: Vec<ListItem> = rows.iter().map(|&n| ListItem::new(tree.row(n))).collect();
+            let list = List::new(items)
+                .block(Block::bordered().title(title))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, list_area, &mut state);
+            frame.render_widget(Paragraph::new(status.as_str()), status_area);
+            frame.render_widget(Paragraph::new(help), help_area);
+        })?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let current = state.selected().and_then(|i| rows.get(i)).copied();
+        match (key.code, current) {
+            (KeyCode::Char('q') | KeyCode::Esc, _) => return Ok(false),
+            (KeyCode::Enter, _) => return Ok(true),
+            (KeyCode::Up | KeyCode::Char('k'), _) => state.select_previous(),
+            (KeyCode::Down | KeyCode::Char('j'), _) => state.select_next(),
+            (KeyCode::Char('a'), _) => tree.toggle(0),
+            (KeyCode::Char(' '), Some(node)) => tree.toggle(node),
+            (KeyCode::Right | KeyCode::Char('l'), Some(node)) => tree.nodes[node].expanded = true,
+            (KeyCode::Left | KeyCode::Char('h'), Some(node)) => {
+                if tree.nodes[node].expanded {
+                    tree.nodes[node].expanded = false;
+                } else if let Some(parent) = tree.nodes[node].parent.filter(|&p| p != 0) {
+                    tree.nodes[parent].expanded = false;
+                    state.select(tree.visible().iter().position(|&n| n == parent));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Lets the user pick which pathnames of a package to extract. Returns
+/// `None` when the package should be skipped.
+pub fn choose(input_path: &str, policy: SanitizePolicy) -> Result<Option<Selection>, io::Error> {
+    let index = index_guids(Volumes::open(input_path)?)?;
+    let sizes = index
+        .iter()
+        .map(|(_, path_name, size)| (path_name.clone(), size.unwrap_or_default()))
+        .collect();
+    let mut tree = Tree::new(sizes, policy);
+
+    let mut terminal = ratatui::try_init()?;
+    let confirmed = run(&mut terminal, input_path, &mut tree);
+    ratatui::restore();
+
+    if !confirmed? {
+        return Ok(None);
+    }
+    Ok(Some(select(index, tree.selection())))
+}
+
+fn select(index: Vec<(PathBuf, String, Option<u64>)>, path_names: HashSet<String>) -> Selection {
+    let guids = index
+        .into_iter()
+        .filter(|(_, path_name, _)| path_names.contains(path_name))
+        .map(|(guid, _, _)| guid)
+        .collect();
+    Selection { path_names, guids }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Tree {
+        Tree::new(
+            vec![
+                ("Assets".to_string(), 0),
+                ("Assets/Scenes/Demo.unity".to_string(), 100),
+                ("Assets/Materials/Rock.mat".to_string(), 10),
+                ("Assets\\Materials\\Tree.mat".to_string(), 20),
+            ],
+            SanitizePolicy::Unity,
+        )
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut tree = tree();
+        assert_eq!(tree.nodes[0].leaves, 3);
+        assert_eq!(tree.nodes[0].size, 130);
+
+        // Only the top level is expanded at first, children are sorted.
+        let rows: Vec<&str> = tree
+            .visible()
+            .iter()
+            .map(|&n| tree.nodes[n].name.as_str())
+            .collect();
+        assert_eq!(rows, vec!["Assets"]);
+        let assets = tree.visible()[0];
+        tree.nodes[assets].expanded = true;
+        let rows: Vec<&str> = tree
+            .visible()
+            .iter()
+            .map(|&n| tree.nodes[n].name.as_str())
+            .collect();
+        assert_eq!(rows, vec!["Assets", "Materials", "Scenes"]);
+
+        let materials = tree.visible()[1];
+        tree.toggle(materials);
+        assert_eq!(tree.nodes[assets].selected_leaves, 1);
+        assert_eq!(tree.selected_size(), 100);
+        assert!(tree.row(assets).contains("[-]"));
+        assert!(tree.row(materials).contains("[ ]"));
+
+        // Folders are kept with their content, raw pathnames are returned.
+        let selection = tree.selection();
+        assert!(selection.contains("Assets"));
+        assert!(selection.contains("Assets/Scenes/Demo.unity"));
+        assert!(!selection.contains("Assets\\Materials\\Tree.mat"));
+
+        // A partially selected subtree is fully selected by toggling it.
+        tree.toggle(assets);
+        assert_eq!(tree.selection().len(), 4);
+        tree.toggle(assets);
+        assert!(tree.selection().is_empty());
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(12), "12 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(3 << 30), "3.0 GiB");
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use log::warn;
+use serde_json::{json, Value};
+
+/// Machine-readable progress, one JSON object per line, for `--progress-fd`.
+#[derive(Default)]
+pub struct Progress {
+    output: Option<Mutex<File>>,
+    bytes: AtomicU64,
+    files: AtomicU64,
+    /// Expected number of bytes to write, 0 when unknown.
+    total: AtomicU64,
+}
+
+impl Progress {
+    /// Takes ownership of a file descriptor opened by the caller.
+    #[cfg(unix)]
+    pub fn from_fd(fd: i32) -> Result<Progress, io::Error> {
+        use std::os::fd::{BorrowedFd, FromRawFd};
+
+        if fd < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid file descriptor",
+            ));
+        }
+        // Duplicating checks the descriptor is open before owning it.
+        drop(unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?);
+        let file = unsafe { File::from_raw_fd(fd) };
+        Ok(Progress {
+            output: Some(Mutex::new(file)),
+            ..Default::default()
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn from_fd(_fd: i32) -> Result<Progress, io::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "file descriptors can only be passed on Unix",
+        ))
+    }
+
+    pub fn add_total(&self, bytes: u64) {
+        self.total.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn emit(&self, record: Value) {
+        let Some(output) = &self.output else {
+            return;
+        };
+        let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(output, "{}", record) {
+            warn!("cannot write progress: {}", e);
+        }
+    }
+
+    fn total(&self) -> Option<u64> {
+        Some(self.total.load(Ordering::Relaxed)).filter(|total| *total > 0)
+    }
+
+    pub fn written(&self, path: &str, size: u64) {
+        let bytes = self.bytes.fetch_add(size, Ordering::Relaxed) + size;
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        self.emit(json!({
+            "file": path,
+            "size": size,
+            "files": files,
+            "bytes": bytes,
+            "total": self.total(),
+        }));
+    }
+
+    pub fn failed(&self, path: &str, guid: &str, error: &io::Error) {
+        self.emit(json!({
+            "file": path,
+            "guid": guid,
+            "error": error.to_string(),
+        }));
+    }
+
+    pub fn finish(&self) {
+        self.emit(json!({
+            "done": true,
+            "files": self.files.load(Ordering::Relaxed),
+            "bytes": self.bytes.load(Ordering::Relaxed),
+            "total": self.total(),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let output = tempfile::NamedTempFile::new().unwrap();
+        let progress = Progress {
+            output: Some(Mutex::new(output.reopen().unwrap())),
+            ..Default::default()
+        };
+        progress.written("Assets/a.png", 10);
+        progress.failed("Assets/b.png", "b1", &io::Error::other("disk full"));
+        progress.written("Assets/c.png", 5);
+        progress.finish();
+
+        let lines: Vec<Value> = std::fs::read_to_string(output.path())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["bytes"], 10);
+        assert_eq!(lines[1]["error"], "disk full");
+        assert_eq!(lines[1]["guid"], "b1");
+        assert_eq!(lines[2]["bytes"], 15);
+        assert_eq!(lines[2]["files"], 2);
+        assert_eq!(lines[3]["done"], true);
+        assert!(lines[3]["total"].is_null());
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::transform::has_extension;
+
+/// Compatibility information found in a package's project files.
+#[derive(Debug, PartialEq)]
+pub enum Hint {
+    EditorVersion(String),
+    Dependency { name: String, version: String },
+}
+
+fn editor_version(project_version: &str) -> Option<Hint> {
+    project_version.lines().find_map(|line| {
+        let version = line.strip_prefix("m_EditorVersion:")?.trim();
+        Some(Hint::EditorVersion(version.to_string()))
+    })
+}
+
+fn dependencies(json: &Value) -> impl Iterator<Item = Hint> + '_ {
+    json["dependencies"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, version)| Hint::Dependency {
+            name: name.clone(),
+            version: version.as_str().unwrap_or_default().to_string(),
+        })
+}
+
+fn upm_package(json: &Value) -> Vec<Hint> {
+    let mut hints: Vec<Hint> = dependencies(json).collect();
+    if let Some(unity) = json["unity"].as_str() {
+        let version = match json["unityRelease"].as_str() {
+            Some(release) => format!("{}.{}", unity, release),
+            None => unity.to_string(),
+        };
+        hints.push(Hint::EditorVersion(version));
+    }
+    hints
+}
+
+// Version defines name the packages an assembly reacts to.
+fn assembly_definition(json: &Value) -> Vec<Hint> {
+    json["versionDefines"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|define| {
+            Some(Hint::Dependency {
+                name: define["name"].as_str()?.to_string(),
+                version: define["expression"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Looks for Unity version and package dependency hints in an asset.
+pub fn scan(path: &Path, data: &[u8]) -> Vec<Hint> {
+    let file_name = path.file_name().and_then(|name| name.to_str());
+    let in_packages = path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .is_some_and(|parent| parent == "Packages");
+
+    match file_name {
+        Some("ProjectVersion.txt") => editor_version(&String::from_utf8_lossy(data))
+            .into_iter()
+            .collect(),
+        Some("manifest.json") if in_packages => serde_json::from_slice(data)
+            .map(|json: Value| dependencies(&json).collect())
+            .unwrap_or_default(),
+        Some("package.json") => serde_json::from_slice(data)
+            .map(|json: Value| upm_package(&json))
+            .unwrap_or_default(),
+        _ if has_extension(path, &["asmdef"]) => serde_json::from_slice(data)
+            .map(|json: Value| assembly_definition(&json))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &str, version: &str) -> Hint {
+        Hint::Dependency {
+            name: name.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_scan() {
+        assert_eq!(
+            scan(
+                Path::new("ProjectSettings/ProjectVersion.txt"),
+                b"m_EditorVersion: 2021.3.5f1\nm_EditorVersionWithRevision: x\n"
+            ),
+            vec![Hint::EditorVersion("2021.3.5f1".to_string())]
+        );
+
+        assert_eq!(
+            scan(
+                Path::new("Packages/manifest.json"),
+                br#"{"dependencies": {"com.unity.ugui": "1.0.0"}}"#
+            ),
+            vec![dependency("com.unity.ugui", "1.0.0")]
+        );
+
+        // Only the project manifest lists dependencies.
+        assert!(scan(
+            Path::new("Assets/manifest.json"),
+            br#"{"dependencies": {"a": "1"}}"#
+        )
+        .is_empty());
+
+        assert_eq!(
+            scan(
+                Path::new("Packages/com.vendor.tool/package.json"),
+                br#"{"unity": "2020.3", "unityRelease": "0f1", "dependencies": {"com.unity.mathematics": "1.2.1"}}"#
+            ),
+            vec![
+                dependency("com.unity.mathematics", "1.2.1"),
+                Hint::EditorVersion("2020.3.0f1".to_string())
+            ]
+        );
+
+        assert_eq!(
+            scan(
+                Path::new("Assets/Vendor/Vendor.asmdef"),
+                br#"{"name": "Vendor", "versionDefines": [{"name": "com.unity.render-pipelines.universal", "expression": "12.0", "define": "URP"}]}"#
+            ),
+            vec![dependency("com.unity.render-pipelines.universal", "12.0")]
+        );
+
+        // Broken JSON yields no hints rather than an error.
+        assert!(scan(Path::new("Assets/Broken.asmdef"), b"{").is_empty());
+    }
+}
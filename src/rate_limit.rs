@@ -0,0 +1,68 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct State {
+    last: Option<Instant>,
+    held_back: u64,
+}
+
+/// `--log-interval`: at most one line per extracted file each interval, as
+/// printing 100k of them dominates the run time on slow terminals. Errors
+/// don't go through it.
+#[derive(Default)]
+pub struct RateLimit {
+    interval: Option<Duration>,
+    state: Mutex<State>,
+}
+
+impl RateLimit {
+    pub fn new(interval: Option<Duration>) -> RateLimit {
+        RateLimit {
+            interval,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a line may be printed now, with how many were held back
+    /// since the last one.
+    pub fn check(&self) -> Option<u64> {
+        let Some(interval) = self.interval else {
+            return Some(0);
+        };
+        self.check_at(interval, Instant::now())
+    }
+
+    fn check_at(&self, interval: Duration, now: Instant) -> Option<u64> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.last {
+            Some(last) if now.duration_since(last) < interval => {
+                state.held_back += 1;
+                None
+            }
+            _ => {
+                state.last = Some(now);
+                Some(std::mem::take(&mut state.held_back))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit() {
+        assert_eq!(RateLimit::default().check(), Some(0));
+
+        let second = Duration::from_secs(1);
+        let limit = RateLimit::new(Some(second));
+        let start = Instant::now();
+        assert_eq!(limit.check_at(second, start), Some(0));
+        assert_eq!(limit.check_at(second, start + second / 2), None);
+        assert_eq!(limit.check_at(second, start + second / 2), None);
+        assert_eq!(limit.check_at(second, start + second), Some(2));
+        assert_eq!(limit.check_at(second, start + second * 3), Some(0));
+    }
+}
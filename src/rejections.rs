@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::{error, info};
+use serde_json::{json, Value};
+
+/// Entries the run refused to write, saved to `rejected.json` in the output
+/// directory so they can be inspected afterwards.
+#[derive(Default)]
+pub struct RejectionLog {
+    rejections: Mutex<BTreeMap<PathBuf, Vec<Value>>>,
+}
+
+impl RejectionLog {
+    pub fn record(&self, output_dir: &Path, guid: &str, path_name: &str, reason: impl Display) {
+        let bytes: String = path_name.bytes().map(|b| format!("{:02x}", b)).collect();
+        let rejection = json!({
+            "guid": guid,
+            "pathname": path_name,
+            "bytes": bytes,
+            "reason": reason.to_string(),
+        });
+        let mut rejections = self.rejections.lock().unwrap_or_else(|e| e.into_inner());
+        rejections
+            .entry(output_dir.to_path_buf())
+            .or_default()
+            .push(rejection);
+    }
+
+    /// Writes one report per output directory and forgets about them.
+    pub fn write_reports(&self) {
+        let rejections =
+            std::mem::take(&mut *self.rejections.lock().unwrap_or_else(|e| e.into_inner()));
+        for (output_dir, rejections) in rejections {
+            let report_path = output_dir.join("rejected.json");
+            let report = Value::Array(rejections);
+            let written = serde_json::to_vec_pretty(&report)
+                .map_err(std::io::Error::from)
+                .and_then(|report| std::fs::write(&report_path, report));
+            match written {
+                Ok(()) => info!("rejected entries are listed in {}", report_path.display()),
+                Err(e) => error!("cannot write {}: {}", report_path.display(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejection_log() {
+        let output = tempfile::tempdir().unwrap();
+        let log = RejectionLog::default();
+        log.record(output.path(), "0123", "Assets/../x\n", "contains ..");
+        log.write_reports();
+
+        let report = std::fs::read_to_string(output.path().join("rejected.json")).unwrap();
+        let report: Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report[0]["guid"], "0123");
+        assert_eq!(report[0]["pathname"], "Assets/../x\n");
+        assert_eq!(report[0]["bytes"], "4173736574732f2e2e2f780a");
+        assert_eq!(report[0]["reason"], "contains ..");
+
+        // Reports are only written once.
+        std::fs::remove_file(output.path().join("rejected.json")).unwrap();
+        log.write_reports();
+        assert!(!output.path().join("rejected.json").exists());
+    }
+}
@@ -0,0 +1,72 @@
+/// Decides where an asset goes before it's written, so destinations can
+/// follow a project's own folder conventions.
+pub trait PathResolver: Send + Sync {
+    /// The pathname to write `guid` to instead of `default_path_name`, the
+    /// package's; `None` keeps it.
+    fn resolve(&self, guid: &str, default_path_name: &str) -> Option<String>;
+}
+
+/// `--map-path FROM=TO`: moves whatever is below `FROM` below `TO`, the
+/// longest matching `FROM` winning.
+#[derive(Debug, Default)]
+pub struct PrefixMap {
+    rules: Vec<(String, String)>,
+}
+
+impl PrefixMap {
+    pub fn parse(rules: &[String]) -> Result<PrefixMap, String> {
+        let mut parsed = Vec::new();
+        for rule in rules {
+            let (from, to) = rule
+                .split_once('=')
+                .map(|(from, to)| (from.trim_end_matches('/'), to.trim_end_matches('/')))
+                .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+                .ok_or_else(|| format!("{:?} isn't FROM=TO", rule))?;
+            parsed.push((from.to_string(), to.to_string()));
+        }
+        parsed.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        Ok(PrefixMap { rules: parsed })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+impl PathResolver for PrefixMap {
+    fn resolve(&self, _guid: &str, default_path_name: &str) -> Option<String> {
+        self.rules.iter().find_map(|(from, to)| {
+            let rest = default_path_name.strip_prefix(from.as_str())?;
+            (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", to, rest))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_map() {
+        let rules = [
+            "Assets/Plugins=Assets/ThirdParty/Plugins".to_string(),
+            "Assets/Plugins/X/=Assets/Vendor/X".to_string(),
+        ];
+        let map = PrefixMap::parse(&rules).unwrap();
+        assert_eq!(
+            map.resolve("a1", "Assets/Plugins/X/x.dll").as_deref(),
+            Some("Assets/Vendor/X/x.dll")
+        );
+        assert_eq!(
+            map.resolve("a2", "Assets/Plugins/y.dll").as_deref(),
+            Some("Assets/ThirdParty/Plugins/y.dll")
+        );
+        assert_eq!(
+            map.resolve("f1", "Assets/Plugins").as_deref(),
+            Some("Assets/ThirdParty/Plugins")
+        );
+        assert_eq!(map.resolve("a3", "Assets/PluginsExtra/z.cs"), None);
+        assert!(PrefixMap::parse(&["Assets/Plugins".to_string()]).is_err());
+        assert!(PrefixMap::parse(&["=Assets".to_string()]).is_err());
+    }
+}
@@ -1,20 +1,206 @@
-use log::warn;
 use std::io;
+use std::str::FromStr;
+
+use crate::warnings::warn_once;
 
 const TRIM_CHARS: &[char] = &['\0', ' ', '\n', '\t', '\r', '/', '.'];
 const END_OF_STRING_CHARS: &[char] = &['\0', '\n', '\r'];
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+const PROJECT_ROOTS: &[&str] = &["Assets", "Packages"];
+
+/// How much `--sanitize` trusts the pathnames of a package.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SanitizePolicy {
+    /// Refuse anything a Unity project wouldn't contain.
+    Strict,
+    /// Fix up what Unity itself writes, refuse `..` in folders.
+    #[default]
+    Unity,
+    /// Salvage every pathname, dropping or replacing what can't be written.
+    Permissive,
+}
+
+impl FromStr for SanitizePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(SanitizePolicy::Strict),
+            "unity" => Ok(SanitizePolicy::Unity),
+            "permissive" => Ok(SanitizePolicy::Permissive),
+            _ => Err(format!("unknown sanitize policy {:?}", s)),
+        }
+    }
+}
+
+impl SanitizePolicy {
+    pub fn sanitize(self, path: &str) -> Result<String, io::Error> {
+        match self {
+            SanitizePolicy::Strict => sanitize_strict(path),
+            SanitizePolicy::Unity => sanitize_path(path),
+            SanitizePolicy::Permissive => sanitize_permissive(path),
+        }
+    }
+}
+
+/// Top level folders a package may write to, `--allowed-root`.
+#[derive(Default)]
+pub struct RootPolicy {
+    /// Every root is allowed when empty.
+    pub allowed: Vec<String>,
+    /// Folder, below the output directory, receiving what isn't allowed.
+    pub quarantine: Option<String>,
+}
+
+impl RootPolicy {
+    pub const DEFAULT_ROOTS: &'static [&'static str] = &["Assets", "Packages", "ProjectSettings"];
+
+    /// Checks the root of an already sanitized path.
+    pub fn confine(&self, sanitized_path: String) -> Result<String, io::Error> {
+        let root = sanitized_path.split('/').next().unwrap_or_default();
+        if self.allowed.is_empty() || self.allowed.iter().any(|allowed| allowed == root) {
+            return Ok(sanitized_path);
+        }
+        match &self.quarantine {
+            Some(quarantine) => {
+                warn_once(
+                    "paths moved out of disallowed roots",
+                    format_args!(
+                        "path «{}» is outside of the allowed roots, moving it to {}",
+                        sanitized_path.escape_default(),
+                        quarantine
+                    ),
+                );
+                Ok(format!("{}/{}", quarantine, sanitized_path))
+            }
+            None => Err(rejected(&sanitized_path, "is outside of the allowed roots")),
+        }
+    }
+}
+
+fn is_reserved_name(component: &str) -> bool {
+    let stem = component.split('.').next().unwrap_or_default();
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(stem))
+}
+
+fn is_absolute(path: &str) -> bool {
+    let path = path.trim_start_matches([' ', '\t']);
+    let bytes = path.as_bytes();
+    path.starts_with(['/', '\\'])
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+fn rejected(path: &str, reason: &str) -> io::Error {
+    warn_once(
+        &format!("rejected paths (path {})", reason),
+        format_args!("path «{}» {}, rejecting it", path.escape_default(), reason),
+    );
+    io::Error::new(io::ErrorKind::InvalidInput, format!("path {}", reason))
+}
+
+/// Windows drops the dots and spaces a file or folder name ends with, so
+/// `Ver.1.0./a.cs` is written to another folder than asked, or, through
+/// `\\?\` paths, to one Explorer can't remove.
+fn ends_badly(component: &str) -> bool {
+    component.ends_with(['.', ' '])
+}
+
+fn sanitize_strict(path: &str) -> Result<String, io::Error> {
+    if is_absolute(path) {
+        return Err(rejected(path, "is absolute"));
+    }
+    // sanitize_path quietly drops leading ../, refuse them here.
+    if path.split(['/', '\\']).any(|component| component == "..") {
+        return Err(rejected(path, "contains .."));
+    }
+    // sanitize_path quietly trims these too.
+    if trim_path(path).split('/').any(ends_badly) {
+        return Err(rejected(path, "has a name ending in a dot or a space"));
+    }
+    let sanitized_path = sanitize_path(path)?;
+    if sanitized_path.contains(char::is_control) {
+        return Err(rejected(path, "contains control characters"));
+    }
+    for component in sanitized_path.split('/') {
+        if is_reserved_name(component) {
+            return Err(rejected(path, "contains a reserved Windows name"));
+        }
+    }
+    let root = sanitized_path.split('/').next().unwrap_or_default();
+    if !PROJECT_ROOTS.contains(&root) {
+        return Err(rejected(path, "is outside of Assets and Packages"));
+    }
+    Ok(sanitized_path)
+}
+
+fn sanitize_permissive(path: &str) -> Result<String, io::Error> {
+    let path_end = path.find(END_OF_STRING_CHARS).unwrap_or(path.len());
+    let unified = path[..path_end].replace('\\', "/");
+    let mut components = Vec::new();
+    for component in unified.split('/') {
+        let component = component.trim_matches([' ', '\t']);
+        match component {
+            "" | "." => {}
+            ".." => warn_once(
+                "paths with .. dropped",
+                format_args!("dropping .. from path «{}»", path.escape_default()),
+            ),
+            _ => {
+                let component = component.trim_end_matches(['.', ' ']);
+                if component.is_empty() {
+                    continue;
+                }
+                // A colon would make a drive letter or an alternate data
+                // stream on Windows.
+                let mut component = component.replace(|c: char| c.is_control() || c == ':', "_");
+                if is_reserved_name(&component) {
+                    let stem_len = component.find('.').unwrap_or(component.len());
+                    component.insert(stem_len, '_');
+                }
+                components.push(component);
+            }
+        }
+    }
+    if components.is_empty() {
+        return Err(rejected(path, "is empty"));
+    }
+    Ok(components.join("/"))
+}
+
+/// The pathname with its separators unified and its ends trimmed.
+fn trim_path(path: &str) -> String {
+    // Backslashes first, so "..\\" is trimmed like "../". The end of string
+    // characters are trimmed, then cut at, so "\n00" and "\0abc" both work,
+    // and what they left at the end is trimmed again.
+    let unified = path.replace('\\', "/");
+    let trimmed = unified.trim_matches(TRIM_CHARS);
+    let end = trimmed.find(END_OF_STRING_CHARS).unwrap_or(trimmed.len());
+    trimmed[..end].trim_matches(TRIM_CHARS).to_string()
+}
 
 pub fn sanitize_path(path: &str) -> Result<String, io::Error> {
-    let sanitized_path = path.trim_matches(TRIM_CHARS).replace('\\', "/");
+    let sanitized_path = trim_path(path);
+    let sanitized_path = sanitized_path.as_str();
 
+    if is_absolute(sanitized_path) {
+        return Err(rejected(path, "is absolute"));
+    }
     if let Some(idx) = sanitized_path.rfind('/') {
         let (dir_part, _) = sanitized_path.split_at(idx);
 
         // Check for ".." only in the directory part
         if dir_part.contains("..") {
-            warn!(
-                "path «{}» contains .. in directory part, this isn't supported",
-                path
+            warn_once(
+                "paths with .. in their directory part",
+                format_args!(
+                    "path «{}» contains .. in directory part, this isn't supported",
+                    path
+                ),
             );
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -23,18 +209,83 @@ pub fn sanitize_path(path: &str) -> Result<String, io::Error> {
         }
     }
 
-    match sanitized_path.find(END_OF_STRING_CHARS) {
-        Some(idx) => {
-            let (final_path, _) = sanitized_path.split_at(idx);
-            Ok(final_path.to_string())
-        }
-        None => Ok(sanitized_path),
-    }
+    // Each name, not only the whole pathname, as Windows would; ".." was
+    // refused above, so this only drops "." and empty names.
+    let components: Vec<&str> = sanitized_path
+        .split('/')
+        .map(|component| component.trim_end_matches(['.', ' ']))
+        .filter(|component| !component.is_empty())
+        .collect();
+    Ok(components.join("/"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    /// Pathnames from reported bugs, and what they must become.
+    const REGRESSIONS: &[(&str, Option<&str>)] = &[
+        // Old Unity versions end pathnames with a newline and "00".
+        ("Assets/a.cs\n00", Some("Assets/a.cs")),
+        ("Assets/a.cs\r\n\0", Some("Assets/a.cs")),
+        ("Assets\\Windows\\a.cs", Some("Assets/Windows/a.cs")),
+        ("\0Assets/a.cs", Some("Assets/a.cs")),
+        // Trimmed before the cut, this kept a trailing space.
+        ("Assets/a.cs \nfoo", Some("Assets/a.cs")),
+        // Trimmed before backslashes were replaced, this became absolute.
+        ("..\\etc/passwd", Some("etc/passwd")),
+        ("C:\\Windows\\a.dll", None),
+        ("Assets/../../a.cs", None),
+        // Windows would write these to "Ver.1.0" and "Docs".
+        ("Assets/Ver.1.0./a.cs", Some("Assets/Ver.1.0/a.cs")),
+        (
+            "Assets/Docs ./read me. .txt",
+            Some("Assets/Docs/read me. .txt"),
+        ),
+        ("Assets/./a.cs", Some("Assets/a.cs")),
+        ("Assets/a../b.cs", None),
+    ];
+
+    #[test]
+    fn test_regressions() {
+        for (path, expected) in REGRESSIONS {
+            let sanitized = sanitize_path(path).ok();
+            assert_eq!(sanitized.as_deref(), *expected, "{:?}", path);
+        }
+    }
+
+    /// Pathnames made of what trips sanitizers up, and of anything.
+    fn path_names() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "[a./\\\\ \t\n\r\0:C]{0,24}",
+            proptest::collection::vec(any::<u8>(), 0..48)
+                .prop_map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+        ]
+    }
+
+    fn stays_below_root(path: &str) -> bool {
+        !is_absolute(path) && path.split('/').all(|component| component != "..")
+    }
+
+    proptest! {
+        #[test]
+        fn test_sanitized_paths(path in path_names()) {
+            for policy in [SanitizePolicy::Strict, SanitizePolicy::Unity, SanitizePolicy::Permissive] {
+                let Ok(sanitized) = policy.sanitize(&path) else {
+                    continue;
+                };
+                prop_assert!(stays_below_root(&sanitized), "{:?} gave {:?}", path, sanitized);
+                prop_assert!(!sanitized.contains(END_OF_STRING_CHARS));
+                prop_assert!(!sanitized.split('/').any(ends_badly), "{:?} gave {:?}", path, sanitized);
+                if policy != SanitizePolicy::Unity {
+                    prop_assert!(!sanitized.contains(char::is_control));
+                }
+                let again = policy.sanitize(&sanitized).ok();
+                prop_assert_eq!(again.as_deref(), Some(sanitized.as_str()), "{:?}", policy);
+            }
+        }
+    }
 
     #[test]
     fn test_sanitize_path() {
@@ -71,4 +322,64 @@ mod tests {
             "folder/file.ext"
         );
     }
+
+    #[test]
+    fn test_sanitize_policies() {
+        let strict = SanitizePolicy::Strict;
+        assert_eq!(strict.sanitize("Assets\\a.cs\n00").unwrap(), "Assets/a.cs");
+        assert!(strict.sanitize("/etc/passwd").is_err());
+        assert!(strict.sanitize("C:\\Windows\\a.dll").is_err());
+        assert!(strict.sanitize("Assets/a\tb.cs").is_err());
+        assert!(strict.sanitize("Assets/nul.txt").is_err());
+        assert!(strict.sanitize("ProjectSettings/a.asset").is_err());
+        assert!(strict.sanitize("../Assets/a.cs").is_err());
+        assert!(strict.sanitize("Assets/Ver.1.0./a.cs").is_err());
+        assert!(strict.sanitize("Assets/Docs /a.cs").is_err());
+        assert_eq!(strict.sanitize("Assets/a.cs/\n00").unwrap(), "Assets/a.cs");
+
+        let unity = SanitizePolicy::Unity;
+        assert_eq!(unity.sanitize("/Other/a.cs").unwrap(), "Other/a.cs");
+        assert!(unity.sanitize("Assets/../a.cs").is_err());
+
+        let permissive = SanitizePolicy::Permissive;
+        assert_eq!(
+            permissive.sanitize("Assets/../../a\tb.cs").unwrap(),
+            "Assets/a_b.cs"
+        );
+        assert_eq!(
+            permissive.sanitize("/Assets/CON.txt").unwrap(),
+            "Assets/CON_.txt"
+        );
+        assert_eq!(
+            permissive
+                .sanitize("Assets/Ver.1.0. /.../nul. .txt")
+                .unwrap(),
+            "Assets/Ver.1.0/nul_. .txt"
+        );
+        assert!(permissive.sanitize("../..").is_err());
+    }
+
+    #[test]
+    fn test_root_policy() {
+        let roots = RootPolicy {
+            allowed: vec!["Assets".to_string()],
+            quarantine: None,
+        };
+        assert_eq!(
+            roots.confine("Assets/a.cs".to_string()).unwrap(),
+            "Assets/a.cs"
+        );
+        assert!(roots.confine("Assetsx/a.cs".to_string()).is_err());
+        assert!(roots.confine("home/.bashrc".to_string()).is_err());
+
+        let roots = RootPolicy {
+            quarantine: Some("_quarantine".to_string()),
+            ..roots
+        };
+        assert_eq!(
+            roots.confine("home/.bashrc".to_string()).unwrap(),
+            "_quarantine/home/.bashrc"
+        );
+        assert!(RootPolicy::default().confine("x/y".to_string()).is_ok());
+    }
 }
@@ -0,0 +1,83 @@
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use log::debug;
+
+/// Tries after the first one, the wait doubling each time: 2.5 s in all,
+/// about as long as Defender takes to scan a large DLL.
+const RETRIES: u32 = 8;
+const FIRST_WAIT: Duration = Duration::from_millis(10);
+
+static RETRIED: AtomicU64 = AtomicU64::new(0);
+
+/// Whether another program has the file open without sharing it, as
+/// antiviruses do with the files they scan.
+#[cfg(windows)]
+fn is_sharing_violation(error: &io::Error) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    error.raw_os_error() == Some(ERROR_SHARING_VIOLATION)
+}
+
+#[cfg(not(windows))]
+fn is_sharing_violation(_error: &io::Error) -> bool {
+    false
+}
+
+/// Runs `op` again while another program holds the file, as an antivirus
+/// scanning what was just written does, instead of failing the write.
+pub fn retry<T>(op: impl FnMut() -> Result<T, io::Error>) -> Result<T, io::Error> {
+    retry_if(op, is_sharing_violation)
+}
+
+fn retry_if<T>(
+    mut op: impl FnMut() -> Result<T, io::Error>,
+    busy: impl Fn(&io::Error) -> bool,
+) -> Result<T, io::Error> {
+    let mut wait = FIRST_WAIT;
+    for _ in 0..RETRIES {
+        match op() {
+            Err(e) if busy(&e) => {
+                debug!("{}, trying again in {:?}", e, wait);
+                RETRIED.fetch_add(1, Ordering::Relaxed);
+                std::thread::sleep(wait);
+                wait *= 2;
+            }
+            result => return result,
+        }
+    }
+    op()
+}
+
+/// How many times files were retried since the last call.
+pub fn take_retries() -> u64 {
+    RETRIED.swap(0, Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_if() {
+        let busy = |e: &io::Error| e.kind() == io::ErrorKind::WouldBlock;
+        let mut attempts = 0;
+        let result = retry_if(
+            || {
+                attempts += 1;
+                match attempts {
+                    1 | 2 => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+                    _ => Ok(attempts),
+                }
+            },
+            busy,
+        );
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(take_retries(), 2);
+
+        let result: Result<(), _> =
+            retry_if(|| Err(io::Error::from(io::ErrorKind::NotFound)), busy);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(take_retries(), 0);
+    }
+}
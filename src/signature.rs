@@ -0,0 +1,161 @@
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::pkcs8::DecodePublicKey;
+use ed25519_dalek::{Signature, VerifyingKey};
+use log::info;
+
+use crate::checksum;
+
+/// Reads an ed25519 public key from a PEM file
+/// (`openssl pkey -in key.pem -pubout`).
+pub fn load_public_key(path: &Path) -> Result<VerifyingKey, io::Error> {
+    let pem = std::fs::read_to_string(path)?;
+    VerifyingKey::from_public_key_pem(&pem)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn signature_path(input_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.sig", input_path))
+}
+
+/// Reads a detached signature, either the 64 raw bytes
+/// `openssl pkeyutl -sign -rawin` writes or their hex form.
+fn read_signature(path: &Path) -> Result<Signature, io::Error> {
+    let data = std::fs::read(path)?;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed signature");
+    let bytes: Vec<u8> = if data.len() == Signature::BYTE_SIZE {
+        data
+    } else {
+        let hex = std::str::from_utf8(&data).map_err(|_| invalid())?.trim();
+        if hex.len() != Signature::BYTE_SIZE * 2 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?
+    };
+    Signature::from_slice(&bytes).map_err(|_| invalid())
+}
+
+/// Checks `<package>.sig` against the SHA-256 of the whole package, hashed
+/// as it's read rather than held in memory, leaving the file at its start
+/// again.
+pub fn verify_package(
+    input_path: &str,
+    file: &mut (impl Read + Seek),
+    key: &VerifyingKey,
+) -> Result<(), io::Error> {
+    let signature_path = signature_path(input_path);
+    let signature = read_signature(&signature_path).map_err(|e| {
+        let message = format!("cannot read signature {}: {}", signature_path.display(), e);
+        io::Error::new(e.kind(), message)
+    })?;
+    let digest = checksum::sha256_reader(&mut *file)?;
+    file.rewind()?;
+    key.verify_strict(&digest, &signature).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "signature doesn't match the package",
+        )
+    })?;
+    info!("{} is signed by the expected key", input_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+    use ed25519_dalek::pkcs8::EncodePublicKey;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::fs::File;
+    use std::io::Write;
+
+    #[test]
+    fn test_verify_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let input_path = dir.path().join("a.unitypackage");
+        let input = input_path.to_str().unwrap();
+        std::fs::write(&input_path, b"package bytes").unwrap();
+
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let pem = signing_key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+        let key_path = dir.path().join("key.pem");
+        std::fs::write(&key_path, pem).unwrap();
+        let key = load_public_key(&key_path).unwrap();
+
+        let mut file = File::open(&input_path).unwrap();
+        assert!(verify_package(input, &mut file, &key).is_err());
+
+        let signature = signing_key
+            .sign(&checksum::sha256(b"package bytes"))
+            .to_bytes();
+        std::fs::write(signature_path(input), signature).unwrap();
+        verify_package(input, &mut file, &key).unwrap();
+        assert_eq!(file.stream_position().unwrap(), 0);
+
+        let hex: String = signature.iter().map(|b| format!("{:02x}", b)).collect();
+        std::fs::write(signature_path(input), format!("{}\n", hex)).unwrap();
+        verify_package(input, &mut file, &key).unwrap();
+
+        File::options()
+            .append(true)
+            .open(&input_path)
+            .unwrap()
+            .write_all(b"tampered")
+            .unwrap();
+        assert!(verify_package(input, &mut file, &key).is_err());
+    }
+
+    /// A large package made up as it's read, remembering the largest read.
+    struct Generated {
+        len: u64,
+        position: u64,
+        largest_read: usize,
+    }
+
+    impl Read for Generated {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.largest_read = self.largest_read.max(buf.len());
+            let read = buf.len().min((self.len - self.position) as usize);
+            buf[..read].fill(b'u');
+            self.position += read as u64;
+            Ok(read)
+        }
+    }
+
+    impl Seek for Generated {
+        fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+            assert_eq!(pos, io::SeekFrom::Start(0));
+            self.position = 0;
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_verify_streamed() {
+        let dir = tempfile::tempdir().unwrap();
+        let input_path = dir.path().join("large.unitypackage");
+        let input = input_path.to_str().unwrap();
+        let len = 16 << 20;
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let digest = checksum::sha256(&vec![b'u'; len]);
+        std::fs::write(signature_path(input), signing_key.sign(&digest).to_bytes()).unwrap();
+
+        let mut package = Generated {
+            len: len as u64,
+            position: 0,
+            largest_read: 0,
+        };
+        verify_package(input, &mut package, &signing_key.verifying_key()).unwrap();
+        assert_eq!(package.position, 0);
+        // Read a block at a time, never into a buffer the size of the file.
+        assert!(package.largest_read <= 64 << 10, "{}", package.largest_read);
+    }
+}
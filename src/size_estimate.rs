@@ -0,0 +1,71 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// gzip only stores the uncompressed size modulo 2^32.
+const ISIZE_MODULUS: u64 = 1 << 32;
+
+/// Reads the ISIZE field of the last gzip member, the uncompressed size of
+/// the tar stream modulo 2^32, along with the length of the package.
+fn gzip_isize<R: Read + Seek>(file: &mut R) -> Result<Option<(u64, u64)>, io::Error> {
+    let len = file.seek(SeekFrom::End(0))?;
+    if len < 18 {
+        return Ok(None);
+    }
+    let mut trailer = [0u8; 4];
+    file.seek(SeekFrom::End(-4))?;
+    file.read_exact(&mut trailer)?;
+    Ok(Some((u32::from_le_bytes(trailer) as u64, len)))
+}
+
+/// The least the tar stream can hold: deflate never makes data larger than
+/// its stored blocks do, 5 bytes every 64 KiB, so the stream is at most
+/// that much smaller than the package. Of the sizes with this ISIZE, the
+/// first one that large.
+fn lower_bound(isize: u64, package_len: u64) -> u64 {
+    let floor = package_len.saturating_sub(package_len / 8192 + 64);
+    if isize >= floor {
+        return isize;
+    }
+    isize + (floor - isize).div_ceil(ISIZE_MODULUS) * ISIZE_MODULUS
+}
+
+/// At least how many bytes a package expands to, headers and padding of
+/// the tar stream included, from the gzip trailer alone; leaves the file at
+/// its start again. `None` when it can't be told.
+pub fn estimate<R: Read + Seek>(file: &mut R) -> Option<u64> {
+    let estimate = gzip_isize(file).ok().flatten();
+    file.rewind().ok()?;
+    estimate.map(|(isize, len)| lower_bound(isize, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestUnityPackageBuilder;
+    use std::io::Write;
+
+    #[test]
+    fn test_estimate() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.bin", &[7; 3000])
+            .add_asset("a2", "Assets/b.bin", &[9; 1000])
+            .build();
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&package).unwrap();
+
+        // The tar stream holds the assets plus headers, metas, pathnames
+        // and padding to a whole 10 KiB record.
+        assert_eq!(estimate(&mut file), Some(10240));
+        assert_eq!(file.stream_position().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_lower_bound() {
+        assert_eq!(lower_bound(10240, 300), 10240);
+        // A 5 GiB stream of random data compresses to a bit over 5 GiB,
+        // its ISIZE saying 1 GiB.
+        let five_gib = 5 << 30;
+        assert_eq!(lower_bound(1 << 30, five_gib + 1000), five_gib);
+        // Compressible data gives no such hint, ISIZE is all there is.
+        assert_eq!(lower_bound(3 << 30, 2 << 30), 3 << 30);
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use log::{debug, error, warn};
+
+use crate::output_root::OutputRoot;
+
+/// `--strict`: stop at the first rejected pathname or failed write and
+/// remove the files the run created. Files it replaced stay as written, as
+/// what they held before is gone already.
+#[derive(Default)]
+pub struct StrictMode {
+    enabled: bool,
+    aborted: AtomicBool,
+    /// Each output directory and the files created below it.
+    created: Mutex<Vec<(OutputRoot, Vec<PathBuf>)>>,
+}
+
+impl StrictMode {
+    pub fn new(enabled: bool) -> StrictMode {
+        StrictMode {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    pub fn abort(&self, reason: impl Display) {
+        if self.enabled && !self.aborted.swap(true, Ordering::SeqCst) {
+            error!("aborting extraction: {}", reason);
+        }
+    }
+
+    /// Stops the run as an abort does, with or without `--strict`, as
+    /// `--timeout` has to.
+    pub fn stop(&self, reason: impl Display) {
+        if !self.aborted.swap(true, Ordering::SeqCst) {
+            error!("aborting extraction: {}", reason);
+        }
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::SeqCst)
+    }
+
+    /// Whether writing `path` creates it, to be recorded once written. Only
+    /// looked at with `--strict`.
+    pub fn is_new(&self, root: &OutputRoot, path: &Path) -> bool {
+        self.enabled && !root.exists(path)
+    }
+
+    /// Remembers a file `is_new` said the run created.
+    pub fn record(&self, root: &OutputRoot, path: &Path) {
+        let mut created = self.created.lock().unwrap_or_else(|e| e.into_inner());
+        let index = match created.iter().position(|(r, _)| r.path() == root.path()) {
+            Some(index) => index,
+            None => match root.try_clone() {
+                Ok(root) => {
+                    created.push((root, Vec::new()));
+                    created.len() - 1
+                }
+                Err(e) => {
+                    warn!("cannot keep track of {}: {}", path.display(), e);
+                    return;
+                }
+            },
+        };
+        created[index].1.push(path.to_path_buf());
+    }
+
+    /// Removes the recorded files along with the folders left empty, up to
+    /// the output directory, then gets ready for another run.
+    pub fn clean_up(&self) {
+        let created = std::mem::take(&mut *self.created.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut removed = 0;
+        for (root, paths) in created {
+            for path in paths {
+                match root.remove_file(&path) {
+                    Ok(()) => removed += 1,
+                    Err(e) => warn!("cannot remove {}: {}", path.display(), e),
+                }
+                for folder in path.ancestors().skip(1) {
+                    if folder.as_os_str().is_empty() || root.remove_dir(folder).is_err() {
+                        break;
+                    }
+                    debug!("removed empty folder {}", folder.display());
+                }
+            }
+        }
+        if self.enabled {
+            error!("removed {} files written before the abort", removed);
+        }
+        self.aborted.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_up() {
+        let output = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(output.path().join("Assets/Keep")).unwrap();
+        std::fs::write(output.path().join("Assets/Keep/old.txt"), b"old").unwrap();
+
+        let root = OutputRoot::open(output.path()).unwrap();
+        let strict = StrictMode::new(true);
+        for path in [
+            "Assets/Keep/old.txt",
+            "Assets/Keep/new.txt",
+            "Assets/New/Deep/new.txt",
+        ] {
+            if strict.is_new(&root, Path::new(path)) {
+                root.create_file(Path::new(path)).unwrap();
+                strict.record(&root, Path::new(path));
+            }
+        }
+        drop(root);
+        strict.abort("test");
+        assert!(strict.is_aborted());
+        strict.clean_up();
+
+        assert!(!strict.is_aborted());
+        let old = std::fs::read(output.path().join("Assets/Keep/old.txt")).unwrap();
+        assert_eq!(old, b"old");
+        assert!(!output.path().join("Assets/Keep/new.txt").exists());
+        assert!(!output.path().join("Assets/New").exists());
+        assert!(output.path().exists());
+    }
+}
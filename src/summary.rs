@@ -0,0 +1,417 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::time::Duration;
+
+use log::LevelFilter;
+use serde_json::{json, Value};
+
+use crate::baseline;
+use crate::checksum::Digest;
+use crate::file_type::FileType;
+use crate::meta::ImportSettings;
+use crate::native_plugin::NativePlugin;
+use crate::project_hints::Hint;
+use crate::warnings::ExtractionWarning;
+
+/// What a write task reports back once its asset is on disk.
+pub struct ExtractedAsset {
+    pub path: String,
+    pub size: u64,
+    pub file_type: FileType,
+    pub native_plugin: Option<NativePlugin>,
+    pub project_hints: Vec<Hint>,
+    pub warnings: Vec<ExtractionWarning>,
+    /// From creating the file to flushing it.
+    pub write_time: Duration,
+}
+
+/// Writes taking longer than this hint at an antivirus scanning every file
+/// or a slow network mount.
+const SLOW_WRITE: Duration = Duration::from_secs(5);
+/// Warnings listed under each heading, the `--report` has them all.
+const LISTED_WARNINGS: usize = 20;
+
+struct SlowWrite {
+    path: String,
+    size: u64,
+    write_time: Duration,
+}
+
+#[derive(Default)]
+struct TypeTotal {
+    files: u64,
+    bytes: u64,
+}
+
+/// Statistics printed at the end of a run.
+#[derive(Default)]
+pub struct Summary {
+    files: u64,
+    bytes: u64,
+    failures: u64,
+    rejected: u64,
+    unreadable_entries: u64,
+    duplicate_guids: usize,
+    warnings: Vec<ExtractionWarning>,
+    raw_entries: u64,
+    by_type: BTreeMap<FileType, TypeTotal>,
+    native_plugins: Vec<NativePlugin>,
+    slow_writes: Vec<SlowWrite>,
+    /// Times a file was tried again as another program held it.
+    sharing_retries: u64,
+    editor_versions: BTreeSet<String>,
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+    import_settings: BTreeMap<String, ImportSettings>,
+    /// Asset Store metadata of the packages, when found.
+    package_infos: Vec<Value>,
+    /// The SHA-256 of each package file read.
+    inputs: Vec<(String, Digest)>,
+    /// `--baseline`: assets left as they were, and pathnames no package
+    /// has anymore.
+    unchanged: u64,
+    removed: Vec<String>,
+}
+
+type Counts = BTreeMap<String, u64>;
+
+fn count(counts: &mut Counts, value: &str) {
+    *counts.entry(value.to_string()).or_default() += 1;
+}
+
+impl Summary {
+    pub fn add(&mut self, asset: ExtractedAsset) {
+        self.files += 1;
+        self.bytes += asset.size;
+        self.warnings.extend(asset.warnings);
+        let total = self.by_type.entry(asset.file_type).or_default();
+        total.files += 1;
+        total.bytes += asset.size;
+        self.native_plugins.extend(asset.native_plugin);
+        if asset.write_time > SLOW_WRITE {
+            self.slow_writes.push(SlowWrite {
+                path: asset.path,
+                size: asset.size,
+                write_time: asset.write_time,
+            });
+        }
+        for hint in asset.project_hints {
+            match hint {
+                Hint::EditorVersion(version) => {
+                    self.editor_versions.insert(version);
+                }
+                Hint::Dependency { name, version } => {
+                    self.dependencies.entry(name).or_default().insert(version);
+                }
+            }
+        }
+    }
+
+    pub fn add_failures(&mut self, count: u64) {
+        self.failures += count;
+    }
+
+    pub fn add_rejected(&mut self, count: u64) {
+        self.rejected += count;
+    }
+
+    pub fn add_unreadable_entries(&mut self, count: u64) {
+        self.unreadable_entries += count;
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    pub fn unreadable_entries(&self) -> u64 {
+        self.unreadable_entries
+    }
+
+    pub fn add_duplicate_guids(&mut self, count: usize) {
+        self.duplicate_guids += count;
+    }
+
+    pub fn add_raw_entries(&mut self, count: u64) {
+        self.raw_entries += count;
+    }
+
+    pub fn add_import_settings(&mut self, settings: Vec<(String, ImportSettings)>) {
+        self.import_settings.extend(settings);
+    }
+
+    pub fn add_package_infos(&mut self, infos: Vec<Value>) {
+        self.package_infos.extend(infos);
+    }
+
+    pub fn add_input(&mut self, input_path: String, digest: Digest) {
+        self.inputs.push((input_path, digest));
+    }
+
+    pub fn add_warnings(&mut self, warnings: Vec<ExtractionWarning>) {
+        self.warnings.extend(warnings);
+    }
+
+    pub fn add_sharing_retries(&mut self, count: u64) {
+        self.sharing_retries += count;
+    }
+
+    pub fn add_unchanged(&mut self, count: u64) {
+        self.unchanged += count;
+    }
+
+    pub fn add_removed(&mut self, path_names: Vec<String>) {
+        self.removed.extend(path_names);
+    }
+
+    /// The statistics block, one line each.
+    fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for package in &self.package_infos {
+            lines.push(format!(
+                "{} {} by {}",
+                package["title"].as_str().unwrap_or_default(),
+                package["version"].as_str().unwrap_or_default(),
+                package["publisher"]
+                    .as_str()
+                    .unwrap_or("an unknown publisher")
+            ));
+        }
+        if self.raw_entries > 0 {
+            lines.push(format!("unpacked {} raw entries", self.raw_entries));
+        }
+        lines.push(format!(
+            "extracted {} files, {} bytes, {} failed",
+            self.files, self.bytes, self.failures
+        ));
+        if self.unchanged > 0 {
+            lines.push(format!(
+                "{} files unchanged since the baseline",
+                self.unchanged
+            ));
+        }
+        if !self.removed.is_empty() {
+            lines.push("removed since the baseline, still on disk:".to_string());
+            for path_name in self.removed.iter().take(LISTED_WARNINGS) {
+                lines.push(format!("  {}", path_name.escape_default()));
+            }
+            if self.removed.len() > LISTED_WARNINGS {
+                lines.push(format!(
+                    "  and {} more",
+                    self.removed.len() - LISTED_WARNINGS
+                ));
+            }
+        }
+        if self.rejected > 0 {
+            lines.push(format!("{} pathnames were rejected", self.rejected));
+        }
+        if self.unreadable_entries > 0 {
+            lines.push(format!(
+                "{} archive entries could not be read",
+                self.unreadable_entries
+            ));
+        }
+        for (file_type, total) in &self.by_type {
+            lines.push(format!(
+                "  {}: {} files, {} bytes",
+                file_type, total.files, total.bytes
+            ));
+        }
+        if !self.native_plugins.is_empty() {
+            lines.push("native plugins, check they match your target platforms:".to_string());
+            for plugin in &self.native_plugins {
+                lines.push(format!("  {}", plugin));
+            }
+        }
+        if !self.slow_writes.is_empty() {
+            lines.push("slow writes, check for an antivirus or a slow mount:".to_string());
+            for slow in &self.slow_writes {
+                lines.push(format!(
+                    "  {} ({} bytes) took {:.1}s",
+                    slow.path,
+                    slow.size,
+                    slow.write_time.as_secs_f64()
+                ));
+            }
+        }
+        if self.sharing_retries > 0 {
+            lines.push(format!(
+                "{} writes waited for another program to let go of the file, likely an antivirus; excluding the output folder from scanning avoids it",
+                self.sharing_retries
+            ));
+        }
+        if self.duplicate_guids > 0 {
+            lines.push(format!(
+                "{} GUIDs appeared more than once",
+                self.duplicate_guids
+            ));
+        }
+        let mut groups: BTreeMap<&str, Vec<&ExtractionWarning>> = BTreeMap::new();
+        for warning in &self.warnings {
+            groups.entry(warning.group()).or_default().push(warning);
+        }
+        for (group, warnings) in groups {
+            lines.push(group.to_string());
+            for warning in warnings.iter().take(LISTED_WARNINGS) {
+                lines.push(format!("  {}", warning));
+            }
+            if warnings.len() > LISTED_WARNINGS {
+                lines.push(format!("  and {} more", warnings.len() - LISTED_WARNINGS));
+            }
+        }
+        for version in &self.editor_versions {
+            lines.push(format!("made for Unity {}", version));
+        }
+        if !self.dependencies.is_empty() {
+            lines.push("package dependencies:".to_string());
+            for (name, versions) in &self.dependencies {
+                let versions: Vec<&str> = versions.iter().map(String::as_str).collect();
+                lines.push(format!("  {} {}", name, versions.join(", ")));
+            }
+        }
+        lines
+    }
+
+    /// The statistics block at the default verbosity and above; `-q` hides it
+    /// along with the warnings.
+    pub fn log(&self) {
+        if log::max_level() >= LevelFilter::Warn {
+            self.print();
+        }
+    }
+
+    /// `--summary`: the statistics block whatever the verbosity.
+    pub fn print(&self) {
+        let mut stdout = io::stdout().lock();
+        for line in self.lines() {
+            let _ = writeln!(stdout, "{}", line);
+        }
+    }
+
+    pub fn checksum_mismatches(&self) -> usize {
+        self.warnings
+            .iter()
+            .filter(|warning| matches!(warning, ExtractionWarning::ChecksumMismatch { .. }))
+            .count()
+    }
+
+    pub fn truncated_entries(&self) -> usize {
+        self.warnings
+            .iter()
+            .filter(|warning| matches!(warning, ExtractionWarning::TruncatedEntry { .. }))
+            .count()
+    }
+
+    fn missing_meta(&self) -> BTreeSet<&str> {
+        self.warnings
+            .iter()
+            .filter_map(|warning| match warning {
+                ExtractionWarning::MissingMeta { path_name } => Some(path_name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn import_settings_json(&self) -> Value {
+        let mut assets = serde_json::Map::new();
+        let mut importers = Counts::new();
+        let mut asset_bundles = Counts::new();
+        let mut labels = Counts::new();
+        let mut settings: BTreeMap<&str, Counts> = BTreeMap::new();
+
+        for (path_name, asset) in &self.import_settings {
+            if let Some(importer) = &asset.importer {
+                count(&mut importers, importer);
+            }
+            if let Some(bundle) = &asset.asset_bundle_name {
+                count(&mut asset_bundles, bundle);
+            }
+            for label in &asset.labels {
+                count(&mut labels, label);
+            }
+            for (key, value) in &asset.settings {
+                count(settings.entry(key).or_default(), value);
+            }
+            assets.insert(
+                path_name.clone(),
+                json!({
+                    "importer": asset.importer,
+                    "asset_bundle_name": asset.asset_bundle_name,
+                    "asset_bundle_variant": asset.asset_bundle_variant,
+                    "labels": asset.labels,
+                    "settings": asset.settings,
+                }),
+            );
+        }
+
+        json!({
+            "importers": importers,
+            "asset_bundles": asset_bundles,
+            "labels": labels,
+            "settings": settings,
+            "assets": assets,
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let types: BTreeMap<String, Value> = self
+            .by_type
+            .iter()
+            .map(|(file_type, total)| {
+                let total = json!({"files": total.files, "bytes": total.bytes});
+                (file_type.to_string(), total)
+            })
+            .collect();
+        let native_plugins: Vec<Value> = self
+            .native_plugins
+            .iter()
+            .map(|plugin| {
+                json!({
+                    "path": plugin.path,
+                    "format": plugin.format,
+                    "architectures": plugin
+                        .architectures
+                        .iter()
+                        .map(|a| a.to_string())
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let slow_writes: Vec<Value> = self
+            .slow_writes
+            .iter()
+            .map(|slow| {
+                json!({
+                    "path": slow.path,
+                    "size": slow.size,
+                    "seconds": slow.write_time.as_secs_f64(),
+                })
+            })
+            .collect();
+
+        json!({
+            "files": self.files,
+            "bytes": self.bytes,
+            "failures": self.failures,
+            "unchanged": self.unchanged,
+            "removed": self.removed,
+            "rejected": self.rejected,
+            "unreadable_entries": self.unreadable_entries,
+            "raw_entries": self.raw_entries,
+            "types": types,
+            "native_plugins": native_plugins,
+            "slow_writes": slow_writes,
+            "sharing_retries": self.sharing_retries,
+            "duplicate_guids": self.duplicate_guids,
+            "missing_meta": self.missing_meta(),
+            "warnings": self.warnings.iter().map(ExtractionWarning::to_json).collect::<Vec<_>>(),
+            "editor_versions": self.editor_versions,
+            "dependencies": self.dependencies,
+            "import_settings": self.import_settings_json(),
+            "packages": self.package_infos,
+            "inputs": baseline::inputs_json(&self.inputs),
+        })
+    }
+}
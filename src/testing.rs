@@ -0,0 +1,334 @@
+//! Fixture packages for the unit tests, the integration tests and the
+//! benches, also built with the `testing` feature for `gen-test-package`.
+//! The crate has no library target, so other crates can't use it.
+
+use std::io::{self, Read, Write};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// In which order entries end up in the archive.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EntryOrder {
+    /// As they were added, which is how Unity writes them.
+    #[default]
+    AsAdded,
+    Reversed,
+    /// Every pathname entry before any asset, the worst case for memory.
+    PathnamesFirst,
+}
+
+enum EntryData {
+    Bytes(Vec<u8>),
+    /// `len` copies of `byte`, streamed instead of kept in memory.
+    Repeat {
+        byte: u8,
+        len: u64,
+    },
+}
+
+impl EntryData {
+    fn len(&self) -> u64 {
+        match self {
+            EntryData::Bytes(data) => data.len() as u64,
+            EntryData::Repeat { len, .. } => *len,
+        }
+    }
+
+    fn reader(&self) -> Box<dyn Read + '_> {
+        match self {
+            EntryData::Bytes(data) => Box::new(data.as_slice()),
+            EntryData::Repeat { byte, len } => Box::new(io::repeat(*byte).take(*len)),
+        }
+    }
+}
+
+enum TestEntry {
+    File {
+        header: tar::Header,
+        path: String,
+        data: EntryData,
+    },
+    /// Appended as is, path and checksum included.
+    Raw { header: tar::Header, data: Vec<u8> },
+    Link {
+        header: tar::Header,
+        path: String,
+        target: String,
+    },
+}
+
+impl TestEntry {
+    fn path(&self) -> String {
+        match self {
+            TestEntry::File { path, .. } | TestEntry::Link { path, .. } => path.clone(),
+            TestEntry::Raw { header, .. } => String::from_utf8_lossy(&header.as_old().name)
+                .trim_end_matches('\0')
+                .to_string(),
+        }
+    }
+}
+
+/// Builds in-memory `.unitypackage` archives for tests.
+#[derive(Default)]
+pub struct TestUnityPackageBuilder {
+    entries: Vec<TestEntry>,
+    order: EntryOrder,
+}
+
+impl TestUnityPackageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn order(mut self, order: EntryOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    fn add_file(mut self, path: &str, data: EntryData) -> Self {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len());
+        header.set_mode(0o644);
+        self.entries.push(TestEntry::File {
+            header,
+            path: path.to_string(),
+            data,
+        });
+        self
+    }
+
+    pub fn add_entry(self, path: &str, data: &[u8]) -> Self {
+        self.add_file(path, EntryData::Bytes(data.to_vec()))
+    }
+
+    /// Adds an entry whose name is written as is, bypassing the path checks
+    /// of `tar::Builder`, to craft malicious packages.
+    pub fn add_entry_with_raw_name(mut self, name: &[u8], data: &[u8]) -> Self {
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name);
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        self.entries.push(TestEntry::Raw {
+            header,
+            data: data.to_vec(),
+        });
+        self
+    }
+
+    /// Adds an entry whose header checksum doesn't match, as found in
+    /// damaged downloads.
+    pub fn add_corrupt_entry(mut self, path: &str, data: &[u8]) -> Self {
+        let mut header = tar::Header::new_old();
+        header.set_path(path).unwrap();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        header.as_old_mut().cksum = *b"0000000\0";
+        self.entries.push(TestEntry::Raw {
+            header,
+            data: data.to_vec(),
+        });
+        self
+    }
+
+    pub fn add_directory(mut self, path: &str) -> Self {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(0o755);
+        self.entries.push(TestEntry::File {
+            header,
+            path: path.to_string(),
+            data: EntryData::Bytes(Vec::new()),
+        });
+        self
+    }
+
+    pub fn add_symlink(self, path: &str, target: &str) -> Self {
+        self.add_link(tar::EntryType::Symlink, path, target)
+    }
+
+    pub fn add_hard_link(self, path: &str, target: &str) -> Self {
+        self.add_link(tar::EntryType::Link, path, target)
+    }
+
+    fn add_link(mut self, entry_type: tar::EntryType, path: &str, target: &str) -> Self {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(0);
+        self.entries.push(TestEntry::Link {
+            header,
+            path: path.to_string(),
+            target: target.to_string(),
+        });
+        self
+    }
+
+    /// Adds a regular asset with its meta and pathname entries.
+    pub fn add_asset(self, guid: &str, path_name: &str, data: &[u8]) -> Self {
+        let meta = format!("fileFormatVersion: 2\nguid: {}\n", guid);
+        self.add_asset_with_meta(guid, path_name, data, &meta)
+    }
+
+    pub fn add_asset_with_meta(self, guid: &str, path_name: &str, data: &[u8], meta: &str) -> Self {
+        self.add_entry(&format!("{}/asset", guid), data)
+            .add_entry(&format!("{}/asset.meta", guid), meta.as_bytes())
+            .add_entry(&format!("{}/pathname", guid), path_name.as_bytes())
+    }
+
+    /// Adds an asset of `size` bytes without holding it in memory.
+    pub fn add_large_asset(self, guid: &str, path_name: &str, size: u64) -> Self {
+        let meta = format!("fileFormatVersion: 2\nguid: {}\n", guid);
+        self.add_file(
+            &format!("{}/asset", guid),
+            EntryData::Repeat {
+                byte: b'u',
+                len: size,
+            },
+        )
+        .add_entry(&format!("{}/asset.meta", guid), meta.as_bytes())
+        .add_entry(&format!("{}/pathname", guid), path_name.as_bytes())
+    }
+
+    /// Adds a folder, which has no asset entry.
+    pub fn add_folder(self, guid: &str, path_name: &str) -> Self {
+        let meta = format!("fileFormatVersion: 2\nguid: {}\nfolderAsset: yes\n", guid);
+        self.add_entry(&format!("{}/asset.meta", guid), meta.as_bytes())
+            .add_entry(&format!("{}/pathname", guid), path_name.as_bytes())
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.write_to(Vec::new()).unwrap()
+    }
+
+    /// Writes the package to `writer` as it's built, for packages too large
+    /// to hold in memory.
+    pub fn write_to<W: Write>(mut self, writer: W) -> io::Result<W> {
+        match self.order {
+            EntryOrder::AsAdded => {}
+            EntryOrder::Reversed => self.entries.reverse(),
+            EntryOrder::PathnamesFirst => self
+                .entries
+                .sort_by_key(|entry| !entry.path().ends_with("/pathname")),
+        }
+
+        let mut builder = tar::Builder::new(GzEncoder::new(writer, Compression::fast()));
+        for entry in self.entries {
+            match entry {
+                TestEntry::File {
+                    mut header,
+                    path,
+                    data,
+                } => builder.append_data(&mut header, path, data.reader())?,
+                TestEntry::Raw { header, data } => builder.append(&header, data.as_slice())?,
+                TestEntry::Link {
+                    mut header,
+                    path,
+                    target,
+                } => builder.append_link(&mut header, path, target)?,
+            }
+        }
+        builder.into_inner()?.finish()
+    }
+
+    /// Builds the package and cuts it after `len` bytes, like an
+    /// interrupted download.
+    pub fn build_truncated(self, len: usize) -> Vec<u8> {
+        let mut package = self.build();
+        package.truncate(len);
+        package
+    }
+
+    /// Builds the package with its tar cut after `len` bytes in a complete
+    /// gzip stream, like a packer that died while writing an entry.
+    pub fn build_short(self, len: usize) -> Vec<u8> {
+        self.build_edited_tar(|tar| tar.truncate(len))
+    }
+
+    /// Builds the package with `junk` in place of the end of archive marker,
+    /// as some packers leave.
+    pub fn build_with_junk(self, junk: &[u8]) -> Vec<u8> {
+        self.build_edited_tar(|tar| {
+            tar.truncate(tar.len() - 1024);
+            tar.extend_from_slice(junk);
+        })
+    }
+
+    fn build_edited_tar(self, edit: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+        let mut tar = Vec::new();
+        flate2::read::GzDecoder::new(self.build().as_slice())
+            .read_to_end(&mut tar)
+            .unwrap();
+        edit(&mut tar);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&tar).unwrap();
+        encoder.finish().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+
+    fn entry_names(package: &[u8]) -> Vec<String> {
+        let mut archive = tar::Archive::new(GzDecoder::new(package));
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_builder_knobs() {
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"a")
+            .add_large_asset("a2", "Assets/big.bin", 3 << 20)
+            .order(EntryOrder::PathnamesFirst)
+            .build();
+        let names = entry_names(&package);
+        assert_eq!(names[..2], ["a1/pathname", "a2/pathname"]);
+        assert!(package.len() < 1 << 20);
+
+        let package = TestUnityPackageBuilder::new()
+            .add_entry("a1/asset", b"a")
+            .add_entry("a1/pathname", b"Assets/a.txt")
+            .order(EntryOrder::Reversed)
+            .build();
+        assert_eq!(entry_names(&package), ["a1/pathname", "a1/asset"]);
+
+        let package = TestUnityPackageBuilder::new()
+            .add_corrupt_entry("a1/asset", b"a")
+            .build();
+        let mut archive = tar::Archive::new(GzDecoder::new(package.as_slice()));
+        assert!(archive.entries().unwrap().next().unwrap().is_err());
+
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"a")
+            .build_truncated(20);
+        assert_eq!(package.len(), 20);
+
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"a")
+            .build_short(1000);
+        let mut tar = Vec::new();
+        GzDecoder::new(package.as_slice())
+            .read_to_end(&mut tar)
+            .unwrap();
+        assert_eq!(tar.len(), 1000);
+
+        let package = TestUnityPackageBuilder::new()
+            .add_asset("a1", "Assets/a.txt", b"a")
+            .build_with_junk(b"junk");
+        let mut tar = Vec::new();
+        GzDecoder::new(package.as_slice())
+            .read_to_end(&mut tar)
+            .unwrap();
+        let (entries, junk) = tar.split_at(tar.len() - 4);
+        assert_eq!(junk, b"junk");
+        assert!(entries[entries.len() - 1024..].iter().any(|b| *b != 0));
+    }
+}
@@ -0,0 +1,102 @@
+use std::cell::Cell;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde_json::{json, Value};
+
+static THREADS: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Small numbers are easier to read than thread ids in the viewer.
+    static THREAD: Cell<u64> = const { Cell::new(0) };
+}
+
+fn thread_number() -> u64 {
+    THREAD.with(|thread| {
+        if thread.get() == 0 {
+            thread.set(THREADS.fetch_add(1, Ordering::Relaxed));
+        }
+        thread.get()
+    })
+}
+
+/// `--trace-out`: when each file was decoded, waited for a writer and was
+/// written, in the Trace Event Format read by chrome://tracing and Perfetto.
+#[derive(Default)]
+pub struct Timeline {
+    events: Option<Mutex<Vec<Value>>>,
+    start: Option<Instant>,
+}
+
+impl Timeline {
+    pub fn new(enabled: bool) -> Timeline {
+        if !enabled {
+            return Timeline::default();
+        }
+        Timeline {
+            events: Some(Mutex::new(Vec::new())),
+            start: Some(Instant::now()),
+        }
+    }
+
+    /// Records a phase that began at `started` and ends now.
+    pub fn record(&self, phase: &str, started: Instant, guid: &str, path: &str) {
+        let (Some(events), Some(start)) = (&self.events, self.start) else {
+            return;
+        };
+        let event = json!({
+            "name": phase,
+            "cat": phase,
+            "ph": "X",
+            "ts": started.saturating_duration_since(start).as_micros() as u64,
+            "dur": started.elapsed().as_micros() as u64,
+            "pid": std::process::id(),
+            "tid": thread_number(),
+            "args": {"guid": guid, "path": path},
+        });
+        events.lock().unwrap_or_else(|e| e.into_inner()).push(event);
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), io::Error> {
+        let Some(events) = &self.events else {
+            return Ok(());
+        };
+        let events = events.lock().unwrap_or_else(|e| e.into_inner());
+        let trace = json!({
+            "traceEvents": *events,
+            "displayTimeUnit": "ms",
+        });
+        std::fs::write(path, serde_json::to_vec(&trace)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.json");
+        let timeline = Timeline::new(true);
+        let started = Instant::now();
+        timeline.record("decode", started, "a1", "a1/asset");
+        timeline.record("write", started, "a1", "Assets/a.png");
+        timeline.write(&path).unwrap();
+
+        let trace: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1]["ph"], "X");
+        assert_eq!(events[1]["args"]["path"], "Assets/a.png");
+
+        Timeline::default().record("write", started, "a1", "Assets/a.png");
+        Timeline::default()
+            .write(&dir.path().join("none.json"))
+            .unwrap();
+        assert!(!dir.path().join("none.json").exists());
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{self, Read};
+
+const BLOCK: u64 = 512;
+
+/// The decompressed stream of a package, keeping track of how much the tar
+/// reader took from it.
+pub struct Tracked<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> Tracked<R> {
+    pub fn new(inner: R) -> Tracked<R> {
+        Tracked { inner, position: 0 }
+    }
+}
+
+impl<R: Read> Read for Tracked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+/// Where the header after `entry` starts.
+pub fn next_header<R: Read>(entry: &tar::Entry<'_, R>) -> u64 {
+    let size = entry.header().entry_size().unwrap_or(0);
+    entry.raw_header_position() + BLOCK + size.div_ceil(BLOCK) * BLOCK
+}
+
+/// After an entry header that can't be read, how much is left of the
+/// archive when none of it looks like another header or the end of archive
+/// marker: junk some tool appended after the last entry rather than a
+/// damaged entry of the package. `rest` has
+/// to have just read a whole block where the header after the last good
+/// entry was expected, and has to end cleanly too; an entry cut short or a
+/// corrupt stream isn't junk.
+pub fn garbage_length<R: Read>(
+    mut rest: Tracked<R>,
+    next_header: u64,
+) -> Result<Option<u64>, io::Error> {
+    if rest.position != next_header + BLOCK {
+        return Ok(None);
+    }
+    let mut length = BLOCK;
+    let mut block = [0; BLOCK as usize];
+    loop {
+        let read = read_block(&mut rest, &mut block)?;
+        if read == 0 {
+            return Ok(Some(length));
+        }
+        if read == block.len() && is_tar_block(&block) {
+            return Ok(None);
+        }
+        length += read as u64;
+    }
+}
+
+/// Fills `block` unless the stream ends first, returning how much it read.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8]) -> Result<usize, io::Error> {
+    let mut read = 0;
+    while read < block.len() {
+        match reader.read(&mut block[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+/// Whether `block` is a tar header with a valid checksum, or zeros as the
+/// end of archive marker is made of.
+fn is_tar_block(block: &[u8; BLOCK as usize]) -> bool {
+    if block.iter().all(|b| *b == 0) {
+        return true;
+    }
+    let header = tar::Header::from_byte_slice(block);
+    let sum = block[..148]
+        .iter()
+        .chain(&block[156..])
+        .fold(0, |sum, b| sum + *b as u32)
+        + 8 * b' ' as u32;
+    header.cksum().is_ok_and(|cksum| cksum == sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_first_block(data: &[u8]) -> Tracked<&[u8]> {
+        let mut tracked = Tracked::new(data);
+        read_block(&mut tracked, &mut [0; BLOCK as usize]).unwrap();
+        tracked
+    }
+
+    #[test]
+    fn test_garbage_length() {
+        let junk = b"<html>not found</html>\n".repeat(50);
+        let rest = read_first_block(&junk);
+        assert_eq!(garbage_length(rest, 0).unwrap(), Some(junk.len() as u64));
+        let rest = read_first_block(&junk);
+        assert_eq!(garbage_length(rest, 1024).unwrap(), None);
+
+        let mut header = tar::Header::new_gnu();
+        header.set_path("a1/asset").unwrap();
+        header.set_size(0);
+        header.set_cksum();
+        let mut data = junk[..BLOCK as usize * 2].to_vec();
+        data.extend_from_slice(header.as_bytes());
+        let rest = read_first_block(&data);
+        assert_eq!(garbage_length(rest, 0).unwrap(), None);
+
+        let mut data = junk[..BLOCK as usize].to_vec();
+        data.extend_from_slice(&[0; BLOCK as usize * 2]);
+        let rest = read_first_block(&data);
+        assert_eq!(garbage_length(rest, 0).unwrap(), None);
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use log::{debug, warn};
+
+/// A conversion applied to an asset's bytes right before it is written.
+pub trait Transform: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether this transform applies to an asset extracted to `path`.
+    fn matches(&self, path: &Path) -> bool;
+
+    fn apply(&self, path: &Path, data: Vec<u8>) -> Vec<u8>;
+}
+
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+}
+
+/// Text assets whose line endings are safe to rewrite.
+const TEXT_EXTENSIONS: &[&str] = &[
+    "asmdef", "asmref", "cginc", "compute", "cs", "hlsl", "js", "json", "shader",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eol {
+    Lf,
+    Crlf,
+    Keep,
+}
+
+impl FromStr for Eol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lf" => Ok(Eol::Lf),
+            "crlf" => Ok(Eol::Crlf),
+            "keep" => Ok(Eol::Keep),
+            _ => Err(format!("unknown line ending {:?}", s)),
+        }
+    }
+}
+
+fn to_lf(data: Vec<u8>) -> Vec<u8> {
+    if !data.windows(2).any(|pair| pair == b"\r\n") {
+        return data;
+    }
+
+    let mut converted = Vec::with_capacity(data.len());
+    let mut bytes = data.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        if byte != b'\r' || bytes.peek() != Some(&&b'\n') {
+            converted.push(byte);
+        }
+    }
+    converted
+}
+
+fn to_crlf(data: Vec<u8>) -> Vec<u8> {
+    let mut converted = Vec::with_capacity(data.len() + data.len() / 32);
+    let mut previous = 0;
+    for &byte in &data {
+        if byte == b'\n' && previous != b'\r' {
+            converted.push(b'\r');
+        }
+        converted.push(byte);
+        previous = byte;
+    }
+    converted
+}
+
+/// Rewrites line endings of known text assets, as selected by `--eol`.
+pub struct NormalizeLineEndings {
+    eol: Eol,
+}
+
+impl NormalizeLineEndings {
+    /// Returns `None` for `Eol::Keep`, since there is nothing to do.
+    pub fn new(eol: Eol) -> Option<Self> {
+        match eol {
+            Eol::Keep => None,
+            _ => Some(NormalizeLineEndings { eol }),
+        }
+    }
+}
+
+impl Transform for NormalizeLineEndings {
+    fn name(&self) -> &str {
+        match self.eol {
+            Eol::Crlf => "eol-crlf",
+            _ => "eol-lf",
+        }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        has_extension(path, TEXT_EXTENSIONS)
+    }
+
+    fn apply(&self, _path: &Path, data: Vec<u8>) -> Vec<u8> {
+        match self.eol {
+            Eol::Crlf => to_crlf(data),
+            _ => to_lf(data),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EncodingMode {
+    Keep,
+    Utf8,
+    Report,
+}
+
+impl FromStr for EncodingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(EncodingMode::Keep),
+            "utf8" => Ok(EncodingMode::Utf8),
+            "report" => Ok(EncodingMode::Report),
+            _ => Err(format!("unknown encoding mode {:?}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TextEncoding {
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+fn detect_encoding(data: &[u8]) -> Option<TextEncoding> {
+    if data.starts_with(&[0xef, 0xbb, 0xbf]) {
+        Some(TextEncoding::Utf8Bom)
+    } else if data.starts_with(&[0xff, 0xfe]) {
+        Some(TextEncoding::Utf16Le)
+    } else if data.starts_with(&[0xfe, 0xff]) {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+fn utf16_to_utf8(data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Option<Vec<u8>> {
+    let pairs = data[2..].chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    let units = pairs.map(|pair| from_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .ok()
+        .map(String::into_bytes)
+}
+
+/// Finds text assets stored with a BOM or as UTF-16, as selected by
+/// `--encoding`, and either reports them or re-encodes them to plain UTF-8.
+pub struct NormalizeEncoding {
+    mode: EncodingMode,
+}
+
+impl NormalizeEncoding {
+    /// Returns `None` for `EncodingMode::Keep`, since there is nothing to do.
+    pub fn new(mode: EncodingMode) -> Option<Self> {
+        match mode {
+            EncodingMode::Keep => None,
+            _ => Some(NormalizeEncoding { mode }),
+        }
+    }
+}
+
+impl Transform for NormalizeEncoding {
+    fn name(&self) -> &str {
+        match self.mode {
+            EncodingMode::Report => "encoding-report",
+            _ => "encoding-utf8",
+        }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        has_extension(path, TEXT_EXTENSIONS)
+    }
+
+    fn apply(&self, path: &Path, data: Vec<u8>) -> Vec<u8> {
+        let Some(encoding) = detect_encoding(&data) else {
+            return data;
+        };
+        if self.mode == EncodingMode::Report {
+            warn!("{:?} is encoded as {:?}", path, encoding);
+            return data;
+        }
+
+        let converted = match encoding {
+            TextEncoding::Utf8Bom => Some(data[3..].to_vec()),
+            TextEncoding::Utf16Le => utf16_to_utf8(&data, u16::from_le_bytes),
+            TextEncoding::Utf16Be => utf16_to_utf8(&data, u16::from_be_bytes),
+        };
+        match converted {
+            Some(converted) => converted,
+            None => {
+                warn!("{:?} is not valid {:?}, keeping it as is", path, encoding);
+                data
+            }
+        }
+    }
+}
+
+/// Converts Windows line endings to Unix ones in scripts and shaders.
+pub struct CrlfToLf;
+
+impl Transform for CrlfToLf {
+    fn name(&self) -> &str {
+        "crlf-to-lf"
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        has_extension(path, &["cs", "shader"])
+    }
+
+    fn apply(&self, _path: &Path, data: Vec<u8>) -> Vec<u8> {
+        to_lf(data)
+    }
+}
+
+/// Transforms known by name, to be selected from the command line.
+pub struct TransformRegistry {
+    transforms: BTreeMap<String, Arc<dyn Transform>>,
+}
+
+impl TransformRegistry {
+    pub fn with_builtins() -> Self {
+        let mut registry = TransformRegistry {
+            transforms: BTreeMap::new(),
+        };
+        registry.register(Arc::new(CrlfToLf));
+        registry
+    }
+
+    pub fn register(&mut self, transform: Arc<dyn Transform>) {
+        self.transforms
+            .insert(transform.name().to_string(), transform);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Transform>> {
+        self.transforms.get(name).cloned()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.transforms.keys().map(String::as_str)
+    }
+}
+
+/// The transforms enabled for a run, applied in order.
+#[derive(Clone, Default)]
+pub struct TransformChain {
+    transforms: Vec<Arc<dyn Transform>>,
+}
+
+impl TransformChain {
+    pub fn push(&mut self, transform: Arc<dyn Transform>) {
+        self.transforms.push(transform);
+    }
+
+    pub fn apply(&self, path: &Path, mut data: Vec<u8>) -> Vec<u8> {
+        for transform in self.transforms.iter().filter(|t| t.matches(path)) {
+            debug!("applying {} to {:?}", transform.name(), path);
+            data = transform.apply(path, data);
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crlf_to_lf() {
+        let mut chain = TransformChain::default();
+        chain.push(
+            TransformRegistry::with_builtins()
+                .get("crlf-to-lf")
+                .unwrap(),
+        );
+
+        // Scripts get their line endings converted, lone \r are kept.
+        assert_eq!(
+            chain.apply(Path::new("Assets/Foo.cs"), b"a\r\nb\rc\r\n".to_vec()),
+            b"a\nb\rc\n"
+        );
+
+        // Extensions are matched case-insensitively.
+        assert_eq!(
+            chain.apply(Path::new("Assets/Foo.SHADER"), b"a\r\n".to_vec()),
+            b"a\n"
+        );
+
+        // Other assets are left untouched.
+        assert_eq!(
+            chain.apply(Path::new("Assets/a.png"), b"a\r\n".to_vec()),
+            b"a\r\n"
+        );
+    }
+
+    #[test]
+    fn test_normalize_line_endings() {
+        let lf = NormalizeLineEndings::new(Eol::Lf).unwrap();
+        let crlf = NormalizeLineEndings::new(Eol::Crlf).unwrap();
+        assert!(NormalizeLineEndings::new(Eol::Keep).is_none());
+
+        assert!(lf.matches(Path::new("Assets/Game.asmdef")));
+        assert!(!lf.matches(Path::new("Assets/a.png")));
+
+        assert_eq!(lf.apply(Path::new("a.cs"), b"a\r\nb\n".to_vec()), b"a\nb\n");
+
+        // Existing CRLF are not doubled.
+        assert_eq!(
+            crlf.apply(Path::new("a.cs"), b"a\r\nb\nc".to_vec()),
+            b"a\r\nb\r\nc"
+        );
+    }
+
+    #[test]
+    fn test_normalize_encoding() {
+        let utf8 = NormalizeEncoding::new(EncodingMode::Utf8).unwrap();
+        let report = NormalizeEncoding::new(EncodingMode::Report).unwrap();
+        let path = Path::new("Assets/Foo.cs");
+
+        // BOM is stripped from UTF-8 files.
+        assert_eq!(
+            utf8.apply(path, b"\xef\xbb\xbfa\xc3\xa9".to_vec()),
+            "aé".as_bytes()
+        );
+
+        // UTF-16 in either byte order is re-encoded.
+        assert_eq!(
+            utf8.apply(path, b"\xff\xfea\x00\xe9\x00".to_vec()),
+            "aé".as_bytes()
+        );
+        assert_eq!(
+            utf8.apply(path, b"\xfe\xff\x00a\x00\xe9".to_vec()),
+            "aé".as_bytes()
+        );
+
+        // Truncated UTF-16 is left alone.
+        assert_eq!(utf8.apply(path, b"\xff\xfea".to_vec()), b"\xff\xfea");
+
+        // Plain UTF-8 passes through, and reporting never changes content.
+        assert_eq!(utf8.apply(path, b"abc".to_vec()), b"abc");
+        assert_eq!(
+            report.apply(path, b"\xef\xbb\xbfa".to_vec()),
+            b"\xef\xbb\xbfa"
+        );
+    }
+}
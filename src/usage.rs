@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::sanitize_path::SanitizePolicy;
+
+#[derive(Default)]
+struct Total {
+    files: u64,
+    bytes: u64,
+}
+
+/// `--usage`: how much room a package takes once extracted, per top-level
+/// folder and per extension, from the tar headers alone.
+#[derive(Default)]
+pub struct DiskUsage {
+    folders: BTreeMap<String, Total>,
+    extensions: BTreeMap<String, Total>,
+}
+
+/// Everything lands below `Assets/`, so the folder right below it tells
+/// more than the first component.
+fn top_level_folder(path: &str) -> String {
+    let mut components = path.split('/');
+    let first = components.next().unwrap_or_default();
+    match (components.next(), components.next()) {
+        (Some(second), Some(_)) => format!("{}/{}", first, second),
+        _ => first.to_string(),
+    }
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| "(none)".to_string())
+}
+
+fn totals_json(totals: &BTreeMap<String, Total>) -> Value {
+    totals
+        .iter()
+        .map(|(name, total)| {
+            let total = json!({"files": total.files, "bytes": total.bytes});
+            (name.clone(), total)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+impl DiskUsage {
+    /// Adds the assets of a package index; folders, without asset, are left
+    /// out.
+    pub fn add_package(&mut self, index: Vec<(String, Option<u64>)>, policy: SanitizePolicy) {
+        for (path_name, size) in index {
+            let (Some(size), Ok(path)) = (size, policy.sanitize(&path_name)) else {
+                continue;
+            };
+            for total in [
+                self.folders.entry(top_level_folder(&path)).or_default(),
+                self.extensions.entry(extension(&path)).or_default(),
+            ] {
+                total.files += 1;
+                total.bytes += size;
+            }
+        }
+    }
+
+    pub fn print(&self) -> Result<(), io::Error> {
+        let mut stdout = io::stdout().lock();
+        for (title, totals) in [("folder", &self.folders), ("extension", &self.extensions)] {
+            writeln!(stdout, "by {}:", title)?;
+            let mut totals: Vec<_> = totals.iter().collect();
+            totals.sort_by_key(|(_, total)| std::cmp::Reverse(total.bytes));
+            for (name, total) in totals {
+                writeln!(
+                    stdout,
+                    "  {:>12} bytes {:>6} files  {}",
+                    total.bytes, total.files, name
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "folders": totals_json(&self.folders),
+            "extensions": totals_json(&self.extensions),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_usage() {
+        let mut usage = DiskUsage::default();
+        usage.add_package(
+            vec![
+                ("Assets/Textures".to_string(), None),
+                ("Assets/Textures/Rock.PNG".to_string(), Some(100)),
+                ("Assets\\Textures\\Tree.png".to_string(), Some(50)),
+                ("Assets/Audio/Wind.ogg".to_string(), Some(30)),
+                ("Assets/README".to_string(), Some(5)),
+            ],
+            SanitizePolicy::Unity,
+        );
+        let report = usage.to_json();
+        assert_eq!(report["folders"]["Assets/Textures"]["files"], 2);
+        assert_eq!(report["folders"]["Assets/Textures"]["bytes"], 150);
+        assert_eq!(report["folders"]["Assets/Audio"]["bytes"], 30);
+        assert_eq!(report["folders"]["Assets"]["bytes"], 5);
+        assert_eq!(report["extensions"]["png"]["bytes"], 150);
+        assert_eq!(report["extensions"]["(none)"]["files"], 1);
+    }
+}
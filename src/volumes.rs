@@ -0,0 +1,196 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use log::debug;
+use memmap2::Mmap;
+
+use crate::zip_input::{self, ZipEntryReader};
+
+/// A package split into `pkg.unitypackage.001`, `.002`, ... read back as a
+/// single stream. Any other input is a single volume. A package inside a
+/// zip is read from the zip's volumes.
+pub struct Volumes {
+    files: Vec<File>,
+    /// Offset of each volume in the whole stream, plus its end.
+    offsets: Vec<u64>,
+    current: usize,
+    position: u64,
+    /// `--mmap`: the single volume, mapped in memory.
+    mapped: Option<Mmap>,
+    zipped: Option<Box<ZipEntryReader<Volumes>>>,
+}
+
+fn next_volume(path: &str, number: usize) -> Option<String> {
+    let base = path.strip_suffix(".001")?;
+    Some(format!("{}.{:03}", base, number))
+}
+
+impl Volumes {
+    /// Opens `input_path` and, when it is the first volume of a split
+    /// package, every volume following it. `pack.zip` and
+    /// `pack.zip!inner.unitypackage` open the package in the zip.
+    pub fn open(input_path: &str) -> Result<Volumes, io::Error> {
+        let Some((zip_path, inner)) = zip_input::split(input_path) else {
+            return Volumes::open_files(input_path);
+        };
+        let zipped = ZipEntryReader::open(Volumes::open_files(zip_path)?, inner)?;
+        Ok(Volumes {
+            files: Vec::new(),
+            offsets: vec![0],
+            current: 0,
+            position: 0,
+            mapped: None,
+            zipped: Some(Box::new(zipped)),
+        })
+    }
+
+    fn open_files(input_path: &str) -> Result<Volumes, io::Error> {
+        let mut files = vec![File::open(input_path)?];
+        while let Some(path) = next_volume(input_path, files.len() + 1) {
+            if !Path::new(&path).is_file() {
+                break;
+            }
+            debug!("adding volume {}", path);
+            files.push(File::open(&path)?);
+        }
+
+        let mut offsets = vec![0];
+        for file in &files {
+            let end = offsets[offsets.len() - 1] + file.metadata()?.len();
+            offsets.push(end);
+        }
+        Ok(Volumes {
+            files,
+            offsets,
+            current: 0,
+            position: 0,
+            mapped: None,
+            zipped: None,
+        })
+    }
+
+    /// Reads the package through a memory map instead of read calls, when
+    /// it is a single non-empty volume. The package must not be truncated
+    /// meanwhile, reading the missing pages would kill the process.
+    pub fn map(&mut self) -> Result<(), io::Error> {
+        if let Some(zipped) = &mut self.zipped {
+            return zipped.get_mut().map();
+        }
+        if self.files.len() != 1 || self.len() == 0 {
+            debug!("not mapping a split or empty package");
+            return Ok(());
+        }
+        // SAFETY: the map is only read, and packages aren't written while
+        // they are extracted.
+        self.mapped = Some(unsafe { Mmap::map(&self.files[0])? });
+        Ok(())
+    }
+
+    fn len(&self) -> u64 {
+        self.offsets[self.files.len()]
+    }
+}
+
+impl Read for Volumes {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(zipped) = &mut self.zipped {
+            return zipped.read(buf);
+        }
+        if let Some(mapped) = &self.mapped {
+            let start = self.position.min(mapped.len() as u64) as usize;
+            let read = buf.len().min(mapped.len() - start);
+            buf[..read].copy_from_slice(&mapped[start..start + read]);
+            self.position += read as u64;
+            return Ok(read);
+        }
+        while self.current < self.files.len() {
+            let read = self.files[self.current].read(buf)?;
+            if read > 0 || buf.is_empty() {
+                self.position += read as u64;
+                return Ok(read);
+            }
+            self.current += 1;
+            if let Some(file) = self.files.get_mut(self.current) {
+                file.rewind()?;
+            }
+        }
+        Ok(0)
+    }
+}
+
+impl Seek for Volumes {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        if let Some(zipped) = &mut self.zipped {
+            return zipped.seek(pos);
+        }
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek"))?;
+        if self.mapped.is_some() {
+            self.position = position;
+            return Ok(position);
+        }
+
+        // Past the end stays on the last volume, as with a single file.
+        let current = self
+            .offsets
+            .partition_point(|&offset| offset <= position)
+            .clamp(1, self.files.len())
+            - 1;
+        self.files[current].seek(SeekFrom::Start(position - self.offsets[current]))?;
+        self.current = current;
+        self.position = position;
+        Ok(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("pkg.unitypackage.001");
+        std::fs::write(&first, b"abc").unwrap();
+        std::fs::write(dir.path().join("pkg.unitypackage.002"), b"").unwrap();
+        std::fs::write(dir.path().join("pkg.unitypackage.003"), b"defg").unwrap();
+        std::fs::write(dir.path().join("pkg.unitypackage.005"), b"skipped").unwrap();
+
+        let mut volumes = Volumes::open(first.to_str().unwrap()).unwrap();
+        assert_eq!(volumes.len(), 7);
+        let mut data = String::new();
+        volumes.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "abcdefg");
+
+        assert_eq!(volumes.seek(SeekFrom::End(-5)).unwrap(), 2);
+        let mut tail = [0u8; 3];
+        volumes.read_exact(&mut tail).unwrap();
+        assert_eq!(&tail, b"cde");
+        volumes.rewind().unwrap();
+        data.clear();
+        volumes.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "abcdefg");
+
+        // Only names ending in .001 are looked at for more volumes.
+        let single = Volumes::open(dir.path().join("pkg.unitypackage.003").to_str().unwrap());
+        let mut single = single.unwrap();
+        assert_eq!(single.len(), 4);
+
+        single.map().unwrap();
+        assert!(single.mapped.is_some());
+        single.seek(SeekFrom::Start(1)).unwrap();
+        data.clear();
+        single.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "efg");
+        assert_eq!(single.seek(SeekFrom::End(-4)).unwrap(), 0);
+        single.read_exact(&mut tail).unwrap();
+        assert_eq!(&tail, b"def");
+        volumes.map().unwrap();
+        assert!(volumes.mapped.is_none());
+    }
+}
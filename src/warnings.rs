@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use log::{debug, warn};
+use serde_json::{json, Value};
+
+use crate::file_type::FileType;
+
+/// Something worth a second look that didn't stop the extraction.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtractionWarning {
+    /// An asset no pathname points to, it wasn't extracted.
+    OrphanAsset { guid: String, file_type: FileType },
+    /// A pathname had to be changed to be written.
+    PathSanitized {
+        path_name: String,
+        sanitized: String,
+    },
+    /// Two GUIDs resolve to the same pathname, the last one overwrote the
+    /// first one.
+    DuplicatePathname {
+        path_name: String,
+        first_guid: String,
+        guid: String,
+    },
+    /// An asset without `.meta`, Unity gives it a new GUID on import.
+    MissingMeta { path_name: String },
+    /// A folder asset with content, which Unity never exports.
+    FolderContent {
+        path_name: String,
+        guid: String,
+        size: u64,
+    },
+    /// An asset doesn't match the package's `checksums` entry.
+    ChecksumMismatch {
+        guid: String,
+        path_name: Option<String>,
+    },
+    /// An entry with less data than its header says, the archive ending
+    /// early. What there was is written.
+    TruncatedEntry {
+        entry: String,
+        path_name: Option<String>,
+        expected: u64,
+        actual: u64,
+    },
+    /// An asset without a pathname, extracted to the one `--guid-db` has for
+    /// its GUID.
+    RecoveredPathName { guid: String, path_name: String },
+    /// Junk after the last entry, which download managers sometimes append.
+    TrailingGarbage { bytes: u64 },
+    /// A file edited since the `--baseline` extraction, kept as is.
+    ModifiedLocally { path_name: String, guid: String },
+}
+
+impl ExtractionWarning {
+    /// Heading the warning is grouped under in the summary.
+    pub fn group(&self) -> &'static str {
+        match self {
+            ExtractionWarning::OrphanAsset { .. } => {
+                "assets without a pathname, they were not extracted:"
+            }
+            ExtractionWarning::PathSanitized { .. } => "pathnames changed to be written:",
+            ExtractionWarning::DuplicatePathname { .. } => {
+                "pathnames used by more than one GUID, the last one was kept:"
+            }
+            ExtractionWarning::MissingMeta { .. } => {
+                "assets without a .meta entry, Unity will give them new GUIDs:"
+            }
+            ExtractionWarning::ChecksumMismatch { .. } => {
+                "assets not matching their checksum, the package is damaged:"
+            }
+            ExtractionWarning::FolderContent { .. } => {
+                "folders with content, --keep-folder-content saves it:"
+            }
+            ExtractionWarning::ModifiedLocally { .. } => {
+                "files edited since the baseline, the new version was not extracted:"
+            }
+            ExtractionWarning::TruncatedEntry { .. } => {
+                "entries cut short by the end of the archive, written partially:"
+            }
+            ExtractionWarning::RecoveredPathName { .. } => {
+                "assets without a pathname, named after the GUID database:"
+            }
+            ExtractionWarning::TrailingGarbage { .. } => {
+                "packages followed by junk after their last entry, ignored:"
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            ExtractionWarning::OrphanAsset { guid, file_type } => json!({
+                "kind": "orphan_asset",
+                "guid": guid,
+                "file_type": file_type.to_string(),
+            }),
+            ExtractionWarning::PathSanitized {
+                path_name,
+                sanitized,
+            } => json!({
+                "kind": "path_sanitized",
+                "pathname": path_name,
+                "sanitized": sanitized,
+            }),
+            ExtractionWarning::DuplicatePathname {
+                path_name,
+                first_guid,
+                guid,
+            } => json!({
+                "kind": "duplicate_pathname",
+                "pathname": path_name,
+                "guids": [first_guid, guid],
+            }),
+            ExtractionWarning::MissingMeta { path_name } => json!({
+                "kind": "missing_meta",
+                "pathname": path_name,
+            }),
+            ExtractionWarning::FolderContent {
+                path_name,
+                guid,
+                size,
+            } => json!({
+                "kind": "folder_content",
+                "pathname": path_name,
+                "guid": guid,
+                "size": size,
+            }),
+            ExtractionWarning::ChecksumMismatch { guid, path_name } => json!({
+                "kind": "checksum_mismatch",
+                "guid": guid,
+                "pathname": path_name,
+            }),
+            ExtractionWarning::TruncatedEntry {
+                entry,
+                path_name,
+                expected,
+                actual,
+            } => json!({
+                "kind": "truncated_entry",
+                "entry": entry,
+                "pathname": path_name,
+                "expected_bytes": expected,
+                "actual_bytes": actual,
+                "partial": path_name.is_some(),
+            }),
+            ExtractionWarning::RecoveredPathName { guid, path_name } => json!({
+                "kind": "recovered_pathname",
+                "guid": guid,
+                "pathname": path_name,
+            }),
+            ExtractionWarning::TrailingGarbage { bytes } => json!({
+                "kind": "trailing_garbage",
+                "bytes": bytes,
+            }),
+            ExtractionWarning::ModifiedLocally { path_name, guid } => json!({
+                "kind": "modified_locally",
+                "pathname": path_name,
+                "guid": guid,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ExtractionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractionWarning::OrphanAsset { guid, file_type } => {
+                write!(f, "{} ({})", guid, file_type)
+            }
+            ExtractionWarning::PathSanitized {
+                path_name,
+                sanitized,
+            } => write!(f, "{} => {}", path_name.escape_default(), sanitized),
+            ExtractionWarning::DuplicatePathname {
+                path_name,
+                first_guid,
+                guid,
+            } => write!(
+                f,
+                "{} ({}, {})",
+                path_name.escape_default(),
+                first_guid,
+                guid
+            ),
+            ExtractionWarning::MissingMeta { path_name } => {
+                write!(f, "{}", path_name.escape_default())
+            }
+            ExtractionWarning::FolderContent {
+                path_name,
+                guid,
+                size,
+            } => write!(
+                f,
+                "{} ({}, {} bytes)",
+                path_name.escape_default(),
+                guid,
+                size
+            ),
+            ExtractionWarning::ChecksumMismatch { guid, path_name } => match path_name {
+                Some(path_name) => write!(f, "{} ({})", path_name.escape_default(), guid),
+                None => write!(f, "{}", guid),
+            },
+            ExtractionWarning::ModifiedLocally { path_name, guid } => {
+                write!(f, "{} ({})", path_name.escape_default(), guid)
+            }
+            ExtractionWarning::RecoveredPathName { guid, path_name } => {
+                write!(f, "{} ({})", path_name.escape_default(), guid)
+            }
+            ExtractionWarning::TrailingGarbage { bytes } => write!(f, "{} bytes", bytes),
+            ExtractionWarning::TruncatedEntry {
+                entry,
+                path_name,
+                expected,
+                actual,
+            } => {
+                match path_name {
+                    Some(path_name) => write!(f, "{} ({})", path_name.escape_default(), entry)?,
+                    None => write!(f, "{}", entry)?,
+                }
+                write!(f, ", {} of {} bytes", actual, expected)
+            }
+        }
+    }
+}
+
+/// Warnings logged once per category, the next ones are only counted.
+#[derive(Default)]
+struct Repeated {
+    held_back: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Repeated {
+    const fn new() -> Repeated {
+        Repeated {
+            held_back: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Whether this is the first warning of its category.
+    fn first(&self, category: &str) -> bool {
+        let mut held_back = self.held_back.lock().unwrap_or_else(|e| e.into_inner());
+        match held_back.get_mut(category) {
+            Some(count) => {
+                *count += 1;
+                false
+            }
+            None => {
+                held_back.insert(category.to_string(), 0);
+                true
+            }
+        }
+    }
+
+    fn take(&self) -> BTreeMap<String, u64> {
+        std::mem::take(&mut *self.held_back.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+static REPEATED: Repeated = Repeated::new();
+
+/// Logs the first warning of a category; the next ones only show with -v
+/// and are counted, so thousands of paths needing the same fix don't flood
+/// the log. `category` describes them in the plural.
+pub fn warn_once(category: &str, message: fmt::Arguments<'_>) {
+    if REPEATED.first(category) {
+        warn!("{}", message);
+    } else {
+        debug!("{}", message);
+    }
+}
+
+/// Logs how many warnings of each category were held back, and starts over.
+pub fn log_repeated() {
+    for (category, count) in REPEATED.take() {
+        if count > 0 {
+            warn!("{} more {}, use -v to list them", count, category);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated() {
+        let repeated = Repeated::default();
+        assert!(repeated.first("dropped .."));
+        assert!(!repeated.first("dropped .."));
+        assert!(!repeated.first("dropped .."));
+        assert!(repeated.first("absolute paths"));
+        let held_back = repeated.take();
+        assert_eq!(held_back["dropped .."], 2);
+        assert_eq!(held_back["absolute paths"], 0);
+        assert!(repeated.first("dropped .."));
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{debug, error, info, warn};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::{fs, io};
+
+use crate::{extract_packages, overwrite, ExtractOptions, Package};
+
+// Browsers and download managers write in chunks; a package is only
+// considered complete once its size stops changing for this long.
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+type PendingSet = Arc<Mutex<HashSet<PathBuf>>>;
+
+fn is_unitypackage(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("unitypackage"))
+}
+
+async fn wait_until_settled(path: &Path) -> Result<(), io::Error> {
+    let mut last_len = fs::metadata(path).await?.len();
+    loop {
+        tokio::time::sleep(SETTLE_DELAY).await;
+        let len = fs::metadata(path).await?.len();
+        if len == last_len {
+            return Ok(());
+        }
+        trace_growth(path, last_len, len);
+        last_len = len;
+    }
+}
+
+fn trace_growth(path: &Path, from: u64, to: u64) {
+    debug!(
+        "{} is still growing ({} => {} bytes)",
+        path.display(),
+        from,
+        to
+    );
+}
+
+async fn extract_when_settled(
+    path: PathBuf,
+    output_dir: &Path,
+    options: &Arc<ExtractOptions>,
+    pending: PendingSet,
+) {
+    if let Err(e) = wait_until_settled(&path).await {
+        debug!("ignoring {}: {}", path.display(), e);
+    } else {
+        let package_dir = output_dir.join(path.file_stem().unwrap_or_default());
+        let package = Package {
+            input_path: path.to_string_lossy().to_string(),
+            output_dir: package_dir,
+            selection: None,
+        };
+        // A package dropped again lands in the folder of the previous one.
+        let refused = {
+            let (input_path, package_dir) =
+                (package.input_path.clone(), package.output_dir.clone());
+            let options = options.clone();
+            tokio::task::spawn_blocking(move || {
+                overwrite::refuses(&input_path, &package_dir, &options)
+            })
+            .await
+        };
+        if refused.unwrap_or(true) {
+            pending.lock().unwrap().remove(&path);
+            return;
+        }
+        match extract_packages(vec![package], options).await {
+            Ok(status) if status.open_failed || status.read_error.is_some() => {
+                error!("extraction of {} did not complete", path.display());
+            }
+            Ok(status) => {
+                info!("extracted {}", path.display());
+                status.summary.log();
+            }
+            Err(e) => error!("cannot extract {}: {}", path.display(), e),
+        }
+    }
+    pending.lock().unwrap().remove(&path);
+}
+
+pub async fn watch_directory(
+    watch_dir: &Path,
+    output_dir: &Path,
+    options: Arc<ExtractOptions>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // The receiver only goes away when we stop watching.
+        let _ = sender.send(event);
+    })?;
+    watcher.watch(watch_dir, RecursiveMode::NonRecursive)?;
+    info!("watching {} for new packages", watch_dir.display());
+
+    let pending: PendingSet = Arc::default();
+    while let Some(event) = receiver.recv().await {
+        let event: notify::Event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("error watching {}: {}", watch_dir.display(), e);
+                continue;
+            }
+        };
+
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+        ) {
+            continue;
+        }
+
+        for path in event.paths.into_iter().filter(|p| is_unitypackage(p)) {
+            if !pending.lock().unwrap().insert(path.clone()) {
+                continue;
+            }
+            debug!("new package {}", path.display());
+            let output_dir = output_dir.to_path_buf();
+            let options = options.clone();
+            let pending = pending.clone();
+            tokio::spawn(async move {
+                extract_when_settled(path, &output_dir, &options, pending).await
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//! Packages read from inside a zip, as `pack.zip` holding a single
+//! `.unitypackage` or `pack.zip!Folder/inner.unitypackage`, without
+//! unzipping them first.
+
+use std::io::{self, Read, Seek, SeekFrom, Take};
+
+use flate2::read::DeflateDecoder;
+use log::debug;
+use zip::{CompressionMethod, ZipArchive};
+
+/// Splits `archive.zip!inner.unitypackage` into the zip and the entry,
+/// `None` for the entry when the input is the zip itself. `None` when the
+/// input isn't a zip.
+pub fn split(input_path: &str) -> Option<(&str, Option<&str>)> {
+    let is_zip = |path: &str| {
+        let lower = path.to_ascii_lowercase();
+        lower.ends_with(".zip") || lower.ends_with(".zip.001")
+    };
+    if let Some((zip_path, inner)) = input_path.split_once('!') {
+        if is_zip(zip_path) && !inner.is_empty() {
+            return Some((zip_path, Some(inner)));
+        }
+    }
+    is_zip(input_path).then_some((input_path, None))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The index of the entry named `inner`, or of the only `.unitypackage` of
+/// the zip.
+fn choose_entry<R: Read + Seek>(
+    archive: &ZipArchive<R>,
+    inner: Option<&str>,
+) -> Result<usize, io::Error> {
+    if let Some(inner) = inner {
+        let inner = inner.trim_start_matches('/');
+        return archive.index_for_name(inner).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("no {} in the zip", inner))
+        });
+    }
+    let packages: Vec<(usize, &str)> = (0..archive.len())
+        .filter_map(|index| Some((index, archive.name_for_index(index)?)))
+        .filter(|(_, name)| name.to_ascii_lowercase().ends_with(".unitypackage"))
+        .collect();
+    match packages[..] {
+        [] => Err(invalid("the zip holds no .unitypackage".to_string())),
+        [(index, _)] => Ok(index),
+        _ => {
+            let names: Vec<&str> = packages.iter().map(|(_, name)| *name).collect();
+            Err(invalid(format!(
+                "the zip holds several packages, pick one with zip!name: {}",
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+enum Stream<R> {
+    Stored(Take<R>),
+    Deflated(DeflateDecoder<Take<R>>),
+}
+
+/// A package inside a zip, read as it's decompressed. It can only be read
+/// from its start: rewinding starts decompressing it over, and other seeks
+/// fail, so nothing inflates the entry twice just to look at its end.
+pub struct ZipEntryReader<R> {
+    stream: Option<Stream<R>>,
+    deflated: bool,
+    data_start: u64,
+    compressed_size: u64,
+}
+
+impl<R: Read + Seek> ZipEntryReader<R> {
+    pub fn open(zip: R, inner: Option<&str>) -> Result<ZipEntryReader<R>, io::Error> {
+        let mut archive = ZipArchive::new(zip).map_err(io::Error::from)?;
+        let index = choose_entry(&archive, inner)?;
+        let entry = archive.by_index_raw(index).map_err(io::Error::from)?;
+        if entry.encrypted() {
+            return Err(invalid(format!("{} is encrypted", entry.name())));
+        }
+        let deflated = match entry.compression() {
+            CompressionMethod::Stored => false,
+            CompressionMethod::Deflated => true,
+            method => {
+                return Err(invalid(format!(
+                    "{} is compressed with {}, only stored and deflated entries are supported",
+                    entry.name(),
+                    method
+                )))
+            }
+        };
+        debug!(
+            "reading {} from the zip, {} bytes",
+            entry.name(),
+            entry.size()
+        );
+        let data_start = entry.data_start();
+        let compressed_size = entry.compressed_size();
+        drop(entry);
+
+        let mut reader = ZipEntryReader {
+            stream: None,
+            deflated,
+            data_start,
+            compressed_size,
+        };
+        reader.restart(archive.into_inner())?;
+        Ok(reader)
+    }
+
+    /// Starts reading the entry from its beginning again.
+    fn restart(&mut self, mut zip: R) -> Result<(), io::Error> {
+        zip.seek(SeekFrom::Start(self.data_start))?;
+        let data = zip.take(self.compressed_size);
+        self.stream = Some(match self.deflated {
+            false => Stream::Stored(data),
+            true => Stream::Deflated(DeflateDecoder::new(data)),
+        });
+        Ok(())
+    }
+
+    /// The zip itself.
+    pub fn get_mut(&mut self) -> &mut R {
+        match self.stream.as_mut().expect("zip stream") {
+            Stream::Stored(take) => take.get_mut(),
+            Stream::Deflated(decoder) => decoder.get_mut().get_mut(),
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for ZipEntryReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.stream.as_mut().expect("zip stream") {
+            Stream::Stored(take) => take.read(buf),
+            Stream::Deflated(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+impl<R: Read + Seek> Seek for ZipEntryReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        if pos != SeekFrom::Start(0) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "a package in a zip can only be rewound",
+            ));
+        }
+        let zip = match self.stream.take().expect("zip stream") {
+            Stream::Stored(take) => take.into_inner(),
+            Stream::Deflated(decoder) => decoder.into_inner().into_inner(),
+        };
+        self.restart(zip)?;
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+
+    /// A zip of `files`, deflated or stored, with a comment to find the
+    /// central directory past.
+    fn zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data, deflate) in files {
+            let method = match deflate {
+                true => CompressionMethod::Deflated,
+                false => CompressionMethod::Stored,
+            };
+            let options = SimpleFileOptions::default().compression_method(method);
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.set_comment("comment");
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("dl/pack.zip"), Some(("dl/pack.zip", None)));
+        assert_eq!(split("dl/PACK.ZIP.001"), Some(("dl/PACK.ZIP.001", None)));
+        assert_eq!(
+            split("pack.zip!Rocks/rocks.unitypackage"),
+            Some(("pack.zip", Some("Rocks/rocks.unitypackage")))
+        );
+        assert_eq!(split("rocks.unitypackage"), None);
+        assert_eq!(split("wow!.unitypackage"), None);
+    }
+
+    #[test]
+    fn test_zip_entry_reader() {
+        let package: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        for deflate in [false, true] {
+            let zip = zip(&[
+                ("readme.txt", b"read me", false),
+                ("Rocks/rocks.unitypackage", &package, deflate),
+            ]);
+            let mut reader = ZipEntryReader::open(Cursor::new(&zip), None).unwrap();
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).unwrap();
+            assert_eq!(data, package);
+
+            // Only rewinding is supported, the size estimate can't look at
+            // the end of the entry.
+            assert!(reader.seek(SeekFrom::End(-4)).is_err());
+            reader.rewind().unwrap();
+            let mut head = [0; 4];
+            reader.read_exact(&mut head).unwrap();
+            assert_eq!(head, package[..4]);
+
+            let inner = Some("readme.txt");
+            let mut reader = ZipEntryReader::open(Cursor::new(&zip), inner).unwrap();
+            let mut text = String::new();
+            reader.read_to_string(&mut text).unwrap();
+            assert_eq!(text, "read me");
+        }
+
+        let two = zip(&[
+            ("a.unitypackage", b"a", false),
+            ("b.unitypackage", b"b", false),
+        ]);
+        assert!(ZipEntryReader::open(Cursor::new(&two), None).is_err());
+        assert!(ZipEntryReader::open(Cursor::new(&two), Some("c.unitypackage")).is_err());
+        assert!(ZipEntryReader::open(Cursor::new(b"not a zip".as_slice()), None).is_err());
+    }
+}
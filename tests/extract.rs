@@ -0,0 +1,601 @@
+//! End-to-end runs of the binary over fixture packages, checking the files
+//! it leaves on disk rather than its counters.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use sha2::Digest;
+
+#[allow(dead_code)]
+#[path = "../src/testing.rs"]
+mod testing;
+
+use testing::{EntryOrder, TestUnityPackageBuilder};
+
+/// Extracts `package` into `out` next to it, with `args` added.
+fn run(package: &[u8], args: &[&str]) -> (tempfile::TempDir, Output) {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("fixture.unitypackage");
+    std::fs::write(&input, package).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-unityextractor"))
+        .arg("-o")
+        .arg(dir.path().join("out"))
+        .args(args)
+        .arg(&input)
+        .output()
+        .unwrap();
+    (dir, output)
+}
+
+/// Every file and folder below `root`, folders ending with a slash.
+fn tree(root: &Path) -> BTreeMap<String, Vec<u8>> {
+    fn walk(root: &Path, dir: &Path, tree: &mut BTreeMap<String, Vec<u8>>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let relative = path.strip_prefix(root).unwrap().to_string_lossy();
+            let relative = relative.replace('\\', "/");
+            if path.is_dir() {
+                tree.insert(format!("{}/", relative), Vec::new());
+                walk(root, &path, tree);
+            } else {
+                tree.insert(relative, std::fs::read(&path).unwrap());
+            }
+        }
+    }
+    let mut tree = BTreeMap::new();
+    walk(root, root, &mut tree);
+    tree
+}
+
+fn expected(entries: &[(&str, &[u8])]) -> BTreeMap<String, Vec<u8>> {
+    entries
+        .iter()
+        .map(|(path, data)| (path.to_string(), data.to_vec()))
+        .collect()
+}
+
+fn rocks(order: EntryOrder) -> Vec<u8> {
+    TestUnityPackageBuilder::new()
+        .add_folder("f1", "Assets/Rocks")
+        .add_asset("a1", "Assets/Rocks/granite.png", b"granite")
+        .add_asset("a2", "Assets/Rocks/Scripts/Roll.cs", b"class Roll {}")
+        .add_folder("f2", "Assets/Rocks/Empty")
+        .order(order)
+        .build()
+}
+
+#[test]
+fn test_entry_orders() {
+    let rocks_tree = expected(&[
+        ("Assets/", b""),
+        ("Assets/Rocks/", b""),
+        ("Assets/Rocks/Empty/", b""),
+        ("Assets/Rocks/Scripts/", b""),
+        ("Assets/Rocks/Scripts/Roll.cs", b"class Roll {}"),
+        ("Assets/Rocks/granite.png", b"granite"),
+    ]);
+    for order in [
+        EntryOrder::AsAdded,
+        EntryOrder::Reversed,
+        EntryOrder::PathnamesFirst,
+    ] {
+        let (dir, output) = run(&rocks(order), &[]);
+        assert!(output.status.success(), "{:?}: {:?}", order, output);
+        assert_eq!(tree(&dir.path().join("out")), rocks_tree, "{:?}", order);
+    }
+}
+
+#[test]
+fn test_folder_assets() {
+    let package = TestUnityPackageBuilder::new()
+        .add_directory("f1/asset/")
+        .add_entry("f1/pathname", b"Assets/FromDirectory")
+        .add_entry("f2/pathname", b"Assets/FromSlash/")
+        .add_folder("f3", "Assets/FromMeta")
+        .add_asset("a1", "Assets/FromMeta/a.txt", b"a")
+        .build();
+    let (dir, output) = run(&package, &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.path().join("out")),
+        expected(&[
+            ("Assets/", b""),
+            ("Assets/FromDirectory/", b""),
+            ("Assets/FromMeta/", b""),
+            ("Assets/FromMeta/a.txt", b"a"),
+            ("Assets/FromSlash/", b""),
+        ])
+    );
+}
+
+#[test]
+fn test_long_paths() {
+    // Past Windows' 260 characters, and a 200 characters long name.
+    let deep: Vec<String> = (0..30).map(|i| format!("Level{:02}", i)).collect();
+    let deep_path = format!("Assets/{}/deep.txt", deep.join("/"));
+    let long_name = format!("Assets/{}.txt", "n".repeat(196));
+    let package = TestUnityPackageBuilder::new()
+        .add_asset("a1", &deep_path, b"deep")
+        .add_asset("a2", &long_name, b"long")
+        .build();
+    let (dir, output) = run(&package, &[]);
+    assert!(output.status.success(), "{:?}", output);
+    let out = dir.path().join("out");
+    assert_eq!(std::fs::read(out.join(&deep_path)).unwrap(), b"deep");
+    assert_eq!(std::fs::read(out.join(&long_name)).unwrap(), b"long");
+}
+
+#[test]
+fn test_weird_bytes() {
+    let package = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/Textures/日本語 é.png", b"unicode")
+        .add_asset("a2", "Assets\\Windows\\Style.cs", b"backslashes")
+        .add_asset("a3", "Assets/Old.txt\n00", b"old unity")
+        .add_asset("a4", "../Assets/Climbing.txt", b"leading dots")
+        .add_asset("a5", "Assets/Binary.bin", &[0, 0xff, 0x80, b'\n', 0])
+        .build();
+    let (dir, output) = run(&package, &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.path().join("out")),
+        expected(&[
+            ("Assets/", b""),
+            ("Assets/Binary.bin", &[0, 0xff, 0x80, b'\n', 0]),
+            ("Assets/Climbing.txt", b"leading dots"),
+            ("Assets/Old.txt", b"old unity"),
+            ("Assets/Textures/", b""),
+            ("Assets/Textures/日本語 é.png", b"unicode"),
+            ("Assets/Windows/", b""),
+            ("Assets/Windows/Style.cs", b"backslashes"),
+        ])
+    );
+}
+
+#[test]
+fn test_rejected_paths() {
+    let package = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/../../escape.txt", b"escape")
+        .add_asset("a2", "Assets/kept.txt", b"kept")
+        .build();
+    let (dir, output) = run(&package, &[]);
+    assert_eq!(output.status.code(), Some(5), "{:?}", output);
+    let out = dir.path().join("out");
+    assert_eq!(std::fs::read(out.join("Assets/kept.txt")).unwrap(), b"kept");
+    assert!(!dir.path().join("escape.txt").exists());
+    assert!(out.join("rejected.json").is_file());
+}
+
+#[test]
+fn test_many_and_large_files() {
+    let mut builder = TestUnityPackageBuilder::new();
+    for i in 0..200 {
+        let data = format!("small {}", i);
+        builder = builder.add_asset(
+            &format!("s{:03}", i),
+            &format!("Assets/Small/{:03}.txt", i),
+            data.as_bytes(),
+        );
+    }
+    let package = builder
+        .add_large_asset("l1", "Assets/Large.bin", 3 << 20)
+        .build();
+    for args in [&[][..], &["--group-by-folder", "--queue-depth", "2"]] {
+        let (dir, output) = run(&package, args);
+        assert!(output.status.success(), "{:?}: {:?}", args, output);
+        let out = dir.path().join("out");
+        let small = std::fs::read_dir(out.join("Assets/Small")).unwrap().count();
+        assert_eq!(small, 200);
+        let large = std::fs::read(out.join("Assets/Large.bin")).unwrap();
+        assert_eq!(large.len(), 3 << 20);
+        assert!(large.iter().all(|&b| b == b'u'));
+        assert_eq!(
+            std::fs::read(out.join("Assets/Small/123.txt")).unwrap(),
+            b"small 123"
+        );
+    }
+}
+
+/// The `--report` written for `package`, compared with its snapshot in
+/// `tests/snapshots` so format changes show up in review.
+fn report(package: &[u8], args: &[&str]) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let report_path = dir.path().join("report.json");
+    let mut args = args.to_vec();
+    args.extend(["--report", report_path.to_str().unwrap()]);
+    let (_out, output) = run(package, &args);
+    assert!(output.status.code().is_some(), "{:?}", output);
+    let mut report: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&report_path).unwrap()).unwrap();
+    // The package is in a temporary folder.
+    for input in report["inputs"].as_array_mut().unwrap() {
+        input["path"] = "fixture.unitypackage".into();
+    }
+    serde_json::to_string_pretty(&report).unwrap()
+}
+
+#[test]
+fn test_report_snapshots() {
+    insta::assert_snapshot!("rocks", report(&rocks(EntryOrder::AsAdded), &[]));
+
+    let settings = TestUnityPackageBuilder::new()
+        .add_asset_with_meta(
+            "a1",
+            "Assets/Rocks/granite.png",
+            b"granite",
+            "fileFormatVersion: 2\nguid: a1\nlabels:\n- stone\n\
+             TextureImporter:\n  assetBundleName: rocks\n",
+        )
+        .add_asset("a2", "Assets/Rocks/Roll.cs", b"class Roll {}")
+        .build();
+    insta::assert_snapshot!("import_settings", report(&settings, &[]));
+
+    let damaged = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/kept.txt", b"kept")
+        .add_asset("a2", "Assets/../../escape.txt", b"escape")
+        .add_asset("a3", "Assets/Old.txt\n00", b"old unity")
+        .add_entry("a4/asset", b"no meta")
+        .add_entry("a4/pathname", b"Assets/NoMeta.txt")
+        .add_entry("a5/asset", b"orphan")
+        .add_asset("a6", "Assets/kept.txt", b"duplicate")
+        .add_corrupt_entry("a7/asset", b"corrupt")
+        .build();
+    insta::assert_snapshot!("damaged", report(&damaged, &[]));
+}
+
+#[test]
+fn test_baseline() {
+    let v1 = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/kept.txt", b"kept")
+        .add_asset("a2", "Assets/changed.txt", b"v1")
+        .add_asset("a3", "Assets/moved.txt", b"moved")
+        .add_asset("a4", "Assets/removed.txt", b"removed")
+        .add_asset("a6", "Assets/edited.txt", b"v1")
+        .build();
+    let v2 = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/kept.txt", b"kept")
+        .add_asset("a2", "Assets/changed.txt", b"v2")
+        .add_asset("a3", "Assets/Moved/moved.txt", b"moved")
+        .add_asset("a5", "Assets/new.txt", b"new")
+        .add_asset("a6", "Assets/edited.txt", b"v2")
+        .build();
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("out");
+    let manifest = dir.path().join("manifest.json");
+    let report = dir.path().join("report.json");
+    let input = dir.path().join("fixture.unitypackage");
+    let extract = |package: &[u8], args: &[&str]| {
+        std::fs::write(&input, package).unwrap();
+        Command::new(env!("CARGO_BIN_EXE_rust-unityextractor"))
+            .arg("-o")
+            .arg(&out)
+            .args(args)
+            .arg(&input)
+            .output()
+            .unwrap()
+    };
+    let output = extract(&v1, &["--manifest", manifest.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    // Left alone as it matches the baseline.
+    std::fs::write(out.join("Assets/kept.txt"), b"edited").unwrap();
+    // Kept over the new version, with a warning.
+    std::fs::write(out.join("Assets/edited.txt"), b"edited").unwrap();
+
+    let output = extract(
+        &v2,
+        &[
+            "--baseline",
+            manifest.to_str().unwrap(),
+            "--report",
+            report.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let files = tree(&out);
+    assert_eq!(files["Assets/kept.txt"], b"edited");
+    assert_eq!(files["Assets/changed.txt"], b"v2");
+    assert_eq!(files["Assets/Moved/moved.txt"], b"moved");
+    assert_eq!(files["Assets/new.txt"], b"new");
+    assert_eq!(files["Assets/edited.txt"], b"edited");
+    let report: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&report).unwrap()).unwrap();
+    assert_eq!(report["unchanged"], 1);
+    assert_eq!(
+        report["removed"],
+        serde_json::json!(["Assets/moved.txt", "Assets/removed.txt"])
+    );
+    assert_eq!(report["warnings"][0]["kind"], "modified_locally");
+    assert_eq!(report["warnings"][0]["pathname"], "Assets/edited.txt");
+}
+
+#[test]
+fn test_immutable() {
+    let (dir, output) = run(&rocks(EntryOrder::AsAdded), &["--immutable"]);
+    assert!(output.status.success(), "{:?}", output);
+    let cache = dir.path().join("out/Library/PackageCache");
+    let folders: Vec<String> = std::fs::read_dir(&cache)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(folders.len(), 1, "{:?}", folders);
+    assert!(folders[0].starts_with("fixture@"), "{:?}", folders);
+    let granite = cache.join(&folders[0]).join("Assets/Rocks/granite.png");
+    assert_eq!(std::fs::read(&granite).unwrap(), b"granite");
+    assert!(std::fs::metadata(&granite)
+        .unwrap()
+        .permissions()
+        .readonly());
+
+    // The same package again is left alone.
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-unityextractor"))
+        .arg("-o")
+        .arg(dir.path().join("out"))
+        .args(["--immutable", "-v"])
+        .arg(dir.path().join("fixture.unitypackage"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("is already in"));
+}
+
+#[test]
+fn test_lower_case() {
+    let package = TestUnityPackageBuilder::new()
+        .add_folder("f1", "Assets/Rocks")
+        .add_asset("a1", "Assets/Rocks/Granite.PNG", b"granite")
+        .add_asset("a2", "Assets/rocks/GRANITE.png", b"other granite")
+        .add_asset("a3", "Assets/ROCKS/Roll.cs", b"class Roll {}")
+        .build();
+    let (dir, output) = run(&package, &["--case", "lower"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.path().join("out")),
+        expected(&[
+            ("Assets/", b""),
+            ("Assets/rocks/", b""),
+            ("Assets/rocks/granite.png", b"granite"),
+            ("Assets/rocks/granite~2.png", b"other granite"),
+            ("Assets/rocks/roll.cs", b"class Roll {}"),
+        ])
+    );
+}
+
+#[test]
+fn test_ascii_paths() {
+    let package = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/Textures/Café.png", b"cafe")
+        .add_asset("a2", "Assets/Textures/Cafe\u{301}.png", b"combining")
+        .build();
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("manifest.json");
+    let manifest_arg = manifest.to_str().unwrap();
+    let (out, output) = run(
+        &package,
+        &["--ascii", "transliterate", "--manifest", manifest_arg],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&out.path().join("out")),
+        expected(&[
+            ("Assets/", b""),
+            ("Assets/Textures/", b""),
+            ("Assets/Textures/Cafe.png", b"cafe"),
+            ("Assets/Textures/Cafe~2.png", b"combining"),
+        ])
+    );
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&manifest).unwrap()).unwrap();
+    assert_eq!(
+        manifest["assets"]["a1"]["pathname"],
+        "Assets/Textures/Cafe.png"
+    );
+    assert_eq!(
+        manifest["assets"]["a1"]["original_pathname"],
+        "Assets/Textures/Café.png"
+    );
+
+    let (out, output) = run(&package, &["--ascii", "percent"]);
+    assert!(output.status.success(), "{:?}", output);
+    let percent = out.path().join("out/Assets/Textures/Caf%C3%A9.png");
+    assert_eq!(std::fs::read(percent).unwrap(), b"cafe");
+}
+
+#[test]
+fn test_count() {
+    let (dir, output) = run(&rocks(EntryOrder::PathnamesFirst), &["--count"]);
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.ends_with("fixture.unitypackage: 2 files, 2 folders, 20 bytes\n"),
+        "{}",
+        stdout
+    );
+    assert!(!dir.path().join("out").exists());
+}
+
+#[test]
+fn test_trailing_garbage() {
+    let junk = b"<html><body>Download complete</body></html>\n".repeat(40);
+    let package = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/a.txt", b"a")
+        .build_with_junk(&junk);
+    let (dir, output) = run(&package, &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.path().join("out")),
+        expected(&[("Assets/", b""), ("Assets/a.txt", b"a")])
+    );
+    let (_, output) = run(&package, &["--no-trailing-garbage"]);
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+
+    // An entry cut short isn't junk, even when the stream ends cleanly.
+    let package = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/a.txt", b"a")
+        .add_large_asset("a2", "Assets/b.bin", 1 << 20)
+        .build_short(1 << 19);
+    let (_, output) = run(&package, &[]);
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+}
+
+#[test]
+fn test_input_checksum() {
+    let package = rocks(EntryOrder::AsAdded);
+    let sha256 = format!("{:x}", sha2::Sha256::digest(&package));
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = dir.path().join("manifest.json");
+    let report = report(&package, &["--manifest", manifest_path.to_str().unwrap()]);
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["inputs"][0]["sha256"], sha256);
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&manifest_path).unwrap()).unwrap();
+    assert_eq!(manifest["inputs"][0]["sha256"], sha256);
+    assert!(manifest["inputs"][0]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("fixture.unitypackage"));
+}
+
+#[test]
+fn test_also_to() {
+    let nas = tempfile::tempdir().unwrap();
+    let package = rocks(EntryOrder::AsAdded);
+    let args = ["--also-to", nas.path().to_str().unwrap()];
+    let (dir, output) = run(&package, &args);
+    assert!(output.status.success(), "{:?}", output);
+    let extracted = tree(&dir.path().join("out"));
+    assert!(extracted.contains_key("Assets/Rocks/Empty/"));
+    assert_eq!(tree(nas.path()), extracted);
+
+    // The mirror is checked for files it would replace too.
+    let (_, output) = run(&package, &args);
+    assert_eq!(output.status.code(), Some(6), "{:?}", output);
+
+    let nas = tempfile::tempdir().unwrap();
+    let args = ["--subdir", "--also-to", nas.path().to_str().unwrap()];
+    let (dir, output) = run(&package, &args);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(tree(nas.path()), tree(&dir.path().join("out")));
+    assert!(nas
+        .path()
+        .join("fixture/Assets/Rocks/granite.png")
+        .is_file());
+}
+
+#[test]
+fn test_guid_db() {
+    let library = tempfile::tempdir().unwrap();
+    let guid_db = library.path().join("library.json");
+    let v1 = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/Rocks/granite.png", b"granite v1")
+        .build();
+    let (_, output) = run(&v1, &["--manifest", guid_db.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let v2 = TestUnityPackageBuilder::new()
+        .add_entry("a1/asset", b"granite v2")
+        .add_entry("a1/asset.meta", b"fileFormatVersion: 2\nguid: a1\n")
+        .add_entry("a2/asset", b"orphan")
+        .build();
+    let (dir, output) = run(&v2, &["--guid-db", guid_db.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.path().join("out")),
+        expected(&[
+            ("Assets/", b""),
+            ("Assets/Rocks/", b""),
+            ("Assets/Rocks/granite.png", b"granite v2"),
+        ])
+    );
+}
+
+#[test]
+fn test_map_path() {
+    let package = rocks(EntryOrder::AsAdded);
+    let args = [
+        "--map-path",
+        "Assets/Rocks/Scripts=Assets/Scripts/Rocks",
+        "--map-path",
+        "Assets/Rocks=Assets/ThirdParty/Rocks",
+    ];
+    let (dir, output) = run(&package, &args);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.path().join("out")),
+        expected(&[
+            ("Assets/", b""),
+            ("Assets/Scripts/", b""),
+            ("Assets/Scripts/Rocks/", b""),
+            ("Assets/Scripts/Rocks/Roll.cs", b"class Roll {}"),
+            ("Assets/ThirdParty/", b""),
+            ("Assets/ThirdParty/Rocks/", b""),
+            ("Assets/ThirdParty/Rocks/Empty/", b""),
+            ("Assets/ThirdParty/Rocks/granite.png", b"granite"),
+        ])
+    );
+
+    let (_, output) = run(&package, &["--map-path", "Assets/Rocks"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
+
+#[test]
+fn test_timeouts() {
+    let package = TestUnityPackageBuilder::new()
+        .add_asset("a1", "Assets/small.txt", b"small")
+        .add_large_asset("a2", "Assets/large.bin", 32 << 20)
+        .build();
+    let (dir, output) = run(&package, &["--file-timeout", "0.000001"]);
+    assert_eq!(output.status.code(), Some(4), "{:?}", output);
+    let extracted = tree(&dir.path().join("out"));
+    assert!(!extracted.contains_key("Assets/large.bin"));
+    assert!(extracted.keys().all(|path| !path.ends_with(".part")));
+
+    let reports = tempfile::tempdir().unwrap();
+    let report_path = reports.path().join("report.json");
+    let report_arg = report_path.to_str().unwrap();
+    let (dir, output) = run(&package, &["--timeout", "0.000001", "--report", report_arg]);
+    assert_eq!(output.status.code(), Some(124), "{:?}", output);
+    let extracted = tree(&dir.path().join("out"));
+    assert!(extracted.keys().all(|path| !path.ends_with(".part")));
+    // The run still reports what it got done.
+    assert!(report_path.is_file());
+
+    let (_, output) = run(&package, &["--timeout", "0"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
+
+#[test]
+fn test_background() {
+    let package = rocks(EntryOrder::AsAdded);
+    let (dir, output) = run(&package, &["--background"]);
+    assert!(output.status.success(), "{:?}", output);
+    let extracted = tree(&dir.path().join("out"));
+    assert_eq!(extracted["Assets/Rocks/granite.png"], b"granite");
+}
+
+#[test]
+fn test_summary_shown_by_default() {
+    let package = rocks(EntryOrder::AsAdded);
+    let (_, output) = run(&package, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("extracted 2 files"), "{}", stdout);
+    let (_, output) = run(&package, &["-q"]);
+    assert!(output.stdout.is_empty(), "{:?}", output);
+}
+
+#[test]
+fn test_overwrite_refused() {
+    let package = rocks(EntryOrder::AsAdded);
+    let (dir, output) = run(&package, &[]);
+    assert!(output.status.success(), "{:?}", output);
+    let again = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_rust-unityextractor"))
+            .arg("-o")
+            .arg(dir.path().join("out"))
+            .args(args)
+            .arg(dir.path().join("fixture.unitypackage"))
+            .output()
+            .unwrap()
+    };
+    assert_eq!(again(&[]).status.code(), Some(6));
+    assert!(again(&["--merge"]).status.success());
+}